@@ -0,0 +1,97 @@
+//! How fast `Scanner` finds ops compared to `OpParser`, on big made-up
+//! memory dumps.
+//!
+//! `cargo bench --bench day03 -- [MEGABYTES]`, 64 by default.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use aoc2024::day03::{interp, Parser, Scanner};
+
+/// Ops and near misses to scatter through the dump.
+const PIECES: [&str; 10] = [
+    "mul(12,345)",
+    "mul(7,8)",
+    "do()",
+    "don't()",
+    "mul(1,",
+    "mul[2,3]",
+    "mul(1234,5)",
+    "mmul(4,4)",
+    "do(",
+    "undo()",
+];
+
+/// A tiny xorshift, so every run sees the same dump.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// About `size` bytes of noise, either printable ASCII like the puzzle
+/// input or any bytes at all, with a piece every 64 bytes or so.
+fn dump(size: usize, binary: bool) -> Vec<u8> {
+    let mut rng = Rng(0x2024_0003);
+    let mut bytes: Vec<u8> = Vec::with_capacity(size + 16);
+    while bytes.len() < size {
+        let r = rng.next();
+        if r.is_multiple_of(64) {
+            bytes.extend(PIECES[(r >> 8) as usize % PIECES.len()].as_bytes());
+        } else if binary {
+            bytes.push((r >> 8) as u8);
+        } else {
+            bytes.push(b' ' + ((r >> 8) % 95) as u8);
+        }
+    }
+    bytes
+}
+
+/// The best of a few runs.
+fn time<F: FnMut() -> u64>(mut f: F) -> (u64, Duration) {
+    let mut best = Duration::MAX;
+    let mut sum = 0;
+    for _ in 0..3 {
+        let start = Instant::now();
+        sum = black_box(f());
+        best = best.min(start.elapsed());
+    }
+    (sum, best)
+}
+
+fn main() {
+    let megabytes: usize = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(64);
+    for binary in [false, true] {
+        let bytes = dump(megabytes << 20, binary);
+        // `Parser` needs a `&str`. Anything that isn't UTF-8 can't be part
+        // of an op anyway.
+        let text = String::from_utf8_lossy(&bytes);
+        let (parsed, parser) = time(|| interp(Parser::new(&text).op_parser()));
+        let (scanned, scanner) = time(|| interp(Scanner::new(&bytes)));
+        assert_eq!(parsed, scanned, "the scanners disagree");
+        let rate = |d: Duration| megabytes as f64 / d.as_secs_f64();
+        println!(
+            "{} MiB {}: sum {}",
+            megabytes,
+            if binary { "binary" } else { "text" },
+            parsed
+        );
+        println!("  OpParser {:>10.2?} {:>8.1} MiB/s", parser, rate(parser));
+        println!(
+            "  Scanner  {:>10.2?} {:>8.1} MiB/s, {:.1}x",
+            scanner,
+            rate(scanner),
+            parser.as_secs_f64() / scanner.as_secs_f64()
+        );
+    }
+}
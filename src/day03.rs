@@ -1,27 +1,136 @@
-use std::fs;
+use std::fs::{self, File};
 
-use op::interp;
-use parser::Parser;
+use vm::Program;
 
-pub fn day03() {
+pub use op::{interp, Op};
+pub use parser::{Parser, StreamParser};
+pub use scan::Scanner;
+
+#[derive(clap::Args)]
+#[group(id = "day03")]
+pub struct Options {
+    /// Also run the input as a program, with `add`, `sub`, `push()`,
+    /// `pop()`, `store`, `load`, `jmp` and `out()` as well
+    #[arg(long)]
+    pub vm: bool,
+
+    /// With `--vm`, stop after this many instructions
+    #[arg(long, value_name = "N", default_value_t = 1_000_000)]
+    pub step_limit: usize,
+
+    /// Only find the sum of ops (and run `--vm`), reading the input a chunk
+    /// at a time
+    #[arg(long)]
+    pub stream: bool,
+
+    /// With `--stream`, how many bytes to read at a time
+    #[arg(long, value_name = "BYTES", default_value_t = 1 << 16)]
+    pub chunk_size: usize,
+
+    /// Print every op found, and every `mul(` that was rejected, with where
+    /// it was and what it added to the sum of ops
+    #[arg(long)]
+    pub listing: bool,
+}
+
+pub fn day03(options: &Options) {
+    if options.stream {
+        let stream = || {
+            let file = File::open("day03_input.txt").unwrap();
+            StreamParser::new(file, options.chunk_size)
+        };
+        println!("sum of ops: {}", interp(stream().map(|op| op.unwrap())));
+        if options.vm {
+            run_vm(
+                options,
+                Program::compile(stream().extended().map(|op| op.unwrap())),
+            );
+        }
+        return;
+    }
     let input = fs::read_to_string("day03_input.txt").unwrap();
-    let sum: u64 = Parser::new(&input)
-        .mul_parser()
-        .map(|(l, r)| (l as u64) * (r as u64))
-        .sum();
+    // With nothing but `mul`s to go on, they all count.
+    let sum = interp(Scanner::muls(input.as_bytes()));
     println!("sum of muls: {}", sum);
-    let sum = interp(Parser::new(&input).op_parser());
+    let sum = interp(Scanner::new(input.as_bytes()));
     println!("sum of ops: {}", sum);
+    if options.listing {
+        print_listing(&input);
+    }
+    if options.vm {
+        run_vm(
+            options,
+            Program::compile(Parser::new(&input).extended().op_parser()),
+        );
+    }
+}
+
+fn run_vm(options: &Options, program: Program) {
+    let outcome = vm::run(&program, options.step_limit);
+    for value in &outcome.out {
+        println!("out: {}", value);
+    }
+    println!(
+        "vm: {} after {} steps, stopped by {}",
+        outcome.acc, outcome.steps, outcome.halt
+    );
+    let registers = Vec::from_iter(outcome.registers.iter().map(|r| r.to_string()));
+    println!("registers: {}", registers.join(" "));
+}
+
+/// One line per op: byte offset, line:column, the text, whether ops were
+/// enabled when it came, and what it added to the sum.
+fn print_listing(input: &str) {
+    let mut flag = true;
+    for listed in Parser::new(input).listing() {
+        let place = format!("{:>6} {}:{}", listed.offset, listed.line, listed.column);
+        match listed.op {
+            Ok(op) => {
+                let added = match op {
+                    Op::Mul(left, right) if flag => (left as u64) * (right as u64),
+                    _ => 0,
+                };
+                println!(
+                    "{:<16} {:<12} {:<8} {}",
+                    place,
+                    listed.text,
+                    if flag { "enabled" } else { "disabled" },
+                    added
+                );
+                match op {
+                    Op::Do => flag = true,
+                    Op::Dont => flag = false,
+                    _ => (),
+                }
+            }
+            Err(reject) => println!("{:<16} {:<12} rejected: {}", place, listed.text, reject),
+        }
+    }
 }
 
+mod vm;
+
+mod scan;
+
 mod op {
     pub type Num = u16;
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
     pub enum Op {
         Mul(Num, Num),
         Do,
         Dont,
+        Add(Num, Num),
+        Sub(Num, Num),
+        Push,
+        Pop,
+        /// Relative to this instruction's index.
+        Jmp(i16),
+        Out,
+        /// Copy the accumulator into a register.
+        Store(Num),
+        /// Copy a register into the accumulator.
+        Load(Num),
     }
 
     pub fn interp<I>(ops: I) -> u64
@@ -32,13 +141,13 @@ mod op {
         let mut sum: u64 = 0;
         for op in ops {
             match op {
-                Op::Mul(left, right) => {
-                    if flag {
-                        sum += ((left as u32) * (right as u32)) as u64;
-                    }
+                Op::Mul(left, right) if flag => {
+                    sum += ((left as u32) * (right as u32)) as u64;
                 }
                 Op::Do => flag = true,
                 Op::Dont => flag = false,
+                // Only the VM knows what to do with the rest.
+                _ => (),
             }
         }
         sum
@@ -49,34 +158,115 @@ mod parser {
     //! program = mul*
     //! mul = "mul(" num ',' num ')
     //! num = digit digit? digit?
+    //!
+    //! The extended instruction set adds:
+    //! add = "add(" num ',' num ')'
+    //! sub = "sub(" num ',' num ')'
+    //! push = "push()"
+    //! pop = "pop()"
+    //! store = "store(" num ')'
+    //! load = "load(" num ')'
+    //! jmp = "jmp(" '-'? num ')'
+    //! out = "out()"
 
-    use std::{iter::Peekable, str::Chars};
+    use std::{
+        collections::VecDeque,
+        fmt,
+        io::{self, Read},
+        iter::Peekable,
+        str::Chars,
+    };
 
     use super::op::{Num, Op};
 
     pub struct Parser<'a> {
+        src: &'a str,
         chars: Peekable<Chars<'a>>,
+        extended: bool,
+        /// Where the next char is, as a byte offset and as a 1-based line and
+        /// column.
+        offset: usize,
+        line: usize,
+        column: usize,
+    }
+
+    /// Why an op that started out right didn't parse.
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum Reject {
+        Digit,
+        TooLong,
+        Comma,
+        Close,
+    }
+
+    impl fmt::Display for Reject {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Reject::Digit => write!(f, "expected a digit"),
+                Reject::TooLong => write!(f, "number over three digits"),
+                Reject::Comma => write!(f, "expected ','"),
+                Reject::Close => write!(f, "expected ')'"),
+            }
+        }
+    }
+
+    /// An op, or an attempt at one, and where it was.
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    pub struct Listed<'a> {
+        pub offset: usize,
+        pub line: usize,
+        pub column: usize,
+        /// The source text, up to where it was rejected if it was.
+        pub text: &'a str,
+        pub op: Result<Op, Reject>,
     }
 
     enum WhichOp {
         Mul,
         Do,
         Dont,
+        Add,
+        Sub,
+        Push,
+        Pop,
+        Jmp,
+        Out,
+        Store,
+        Load,
     }
 
     impl<'a> Parser<'a> {
         pub fn new(s: &'a str) -> Self {
             Self {
+                src: s,
                 chars: s.chars().peekable(),
+                extended: false,
+                offset: 0,
+                line: 1,
+                column: 1,
             }
         }
 
+        /// Recognize the extended instruction set as well.
+        pub fn extended(mut self) -> Self {
+            self.extended = true;
+            self
+        }
+
         fn peek(&mut self) -> Option<char> {
             self.chars.peek().copied()
         }
 
         fn advance(&mut self) {
-            self.chars.next();
+            if let Some(c) = self.chars.next() {
+                self.offset += c.len_utf8();
+                if c == '\n' {
+                    self.line += 1;
+                    self.column = 1;
+                } else {
+                    self.column += 1;
+                }
+            }
         }
 
         fn eof(&mut self) -> bool {
@@ -110,27 +300,27 @@ mod parser {
                 .map(|c| c.to_digit(10).unwrap())
         }
 
-        fn num(&mut self) -> Option<Num> {
-            let mut i = self.digit()? as Num;
+        fn num(&mut self) -> Result<Num, Reject> {
+            let mut i = self.digit().ok_or(Reject::Digit)? as Num;
             for _ in 0..2 {
                 if let Some(digit) = self.digit() {
                     i = i * 10 + (digit as Num);
                 }
             }
             if self.digit().is_some() {
-                None
+                Err(Reject::TooLong)
             } else {
-                Some(i)
+                Ok(i)
             }
         }
 
+        fn close(&mut self) -> Result<(), Reject> {
+            self.cmatch(|c| c == ')').map(|_| ()).ok_or(Reject::Close)
+        }
+
         fn try_mul(&mut self) -> Option<(Num, Num)> {
             if self.smatch("mul(") {
-                let left = self.num()?;
-                self.cmatch(|c| c == ',')?;
-                let right = self.num()?;
-                self.cmatch(|c| c == ')')?;
-                Some((left, right))
+                self.args().ok()
             } else {
                 None
             }
@@ -138,22 +328,37 @@ mod parser {
 
         fn which_op(&mut self) -> Option<WhichOp> {
             match self.peek()? {
-                'm' => {
-                    if self.smatch("mul(") {
-                        return Some(WhichOp::Mul);
-                    }
+                'm' if self.smatch("mul(") => {
+                    return Some(WhichOp::Mul);
                 }
-                'd' => {
-                    if self.smatch("do") {
-                        if self.cmatch(|c| c == '(').is_some() {
-                            if self.cmatch(|c| c == ')').is_some() {
-                                return Some(WhichOp::Do);
-                            }
-                        } else if self.smatch("n't()") {
-                            return Some(WhichOp::Dont);
+                'd' if self.smatch("do") => {
+                    if self.cmatch(|c| c == '(').is_some() {
+                        if self.cmatch(|c| c == ')').is_some() {
+                            return Some(WhichOp::Do);
                         }
+                    } else if self.smatch("n't()") {
+                        return Some(WhichOp::Dont);
+                    }
+                }
+                _ if !self.extended => (),
+                'a' if self.smatch("add(") => return Some(WhichOp::Add),
+                's' if self.smatch("s") => {
+                    if self.smatch("ub(") {
+                        return Some(WhichOp::Sub);
+                    } else if self.smatch("tore(") {
+                        return Some(WhichOp::Store);
                     }
                 }
+                'l' if self.smatch("load(") => return Some(WhichOp::Load),
+                'p' if self.smatch("p") => {
+                    if self.smatch("ush()") {
+                        return Some(WhichOp::Push);
+                    } else if self.smatch("op()") {
+                        return Some(WhichOp::Pop);
+                    }
+                }
+                'j' if self.smatch("jmp(") => return Some(WhichOp::Jmp),
+                'o' if self.smatch("out()") => return Some(WhichOp::Out),
                 _ => (),
             }
             None
@@ -167,18 +372,55 @@ mod parser {
             OpParser { p: self }
         }
 
+        /// The same ops as `op_parser`, with where they were, plus every
+        /// one that got as far as its `(` and was then rejected.
+        pub fn listing(self) -> Listing<'a> {
+            Listing { p: self }
+        }
+
+        /// num ',' num ')'
+        fn args(&mut self) -> Result<(Num, Num), Reject> {
+            let left = self.num()?;
+            self.cmatch(|c| c == ',').ok_or(Reject::Comma)?;
+            let right = self.num()?;
+            self.close()?;
+            Ok((left, right))
+        }
+
+        /// num ')'
+        fn register(&mut self) -> Result<Num, Reject> {
+            let r = self.num()?;
+            self.close()?;
+            Ok(r)
+        }
+
         fn try_op(&mut self) -> Option<Op> {
-            match self.which_op()? {
-                WhichOp::Mul => {
-                    let left = self.num()?;
-                    self.cmatch(|c| c == ',')?;
-                    let right = self.num()?;
-                    self.cmatch(|c| c == ')')?;
-                    Some(Op::Mul(left, right))
+            self.op()?.ok()
+        }
+
+        /// `None` if there's no op here at all, or why the one here was
+        /// rejected.
+        fn op(&mut self) -> Option<Result<Op, Reject>> {
+            Some(match self.which_op()? {
+                WhichOp::Mul => self.args().map(|(l, r)| Op::Mul(l, r)),
+                WhichOp::Do => Ok(Op::Do),
+                WhichOp::Dont => Ok(Op::Dont),
+                WhichOp::Add => self.args().map(|(l, r)| Op::Add(l, r)),
+                WhichOp::Sub => self.args().map(|(l, r)| Op::Sub(l, r)),
+                WhichOp::Push => Ok(Op::Push),
+                WhichOp::Pop => Ok(Op::Pop),
+                WhichOp::Jmp => {
+                    let negative = self.cmatch(|c| c == '-').is_some();
+                    self.num().and_then(|offset| {
+                        self.close()?;
+                        let offset = offset as i16;
+                        Ok(Op::Jmp(if negative { -offset } else { offset }))
+                    })
                 }
-                WhichOp::Do => Some(Op::Do),
-                WhichOp::Dont => Some(Op::Dont),
-            }
+                WhichOp::Out => Ok(Op::Out),
+                WhichOp::Store => self.register().map(Op::Store),
+                WhichOp::Load => self.register().map(Op::Load),
+            })
         }
     }
 
@@ -200,6 +442,103 @@ mod parser {
         }
     }
 
+    /// Enough to finish any op that starts before it: the longest,
+    /// `mul(999,999)`, plus the char looked at past each number and the one
+    /// skipped after a rejection.
+    const LOOKAHEAD: usize = 16;
+
+    /// `OpParser` over a reader, holding only about a chunk at a time.
+    pub struct StreamParser<R> {
+        reader: R,
+        chunk: usize,
+        extended: bool,
+        /// What's been read but not scanned, with anything that isn't ASCII
+        /// made `\0`. No op has anything but ASCII in it, so that doesn't
+        /// change what's found, and it can always be scanned as a `&str`.
+        buf: Vec<u8>,
+        done: bool,
+        ops: VecDeque<Op>,
+    }
+
+    impl<R: Read> StreamParser<R> {
+        pub fn new(reader: R, chunk: usize) -> Self {
+            assert!(chunk > 0, "chunk size must be positive");
+            Self {
+                reader,
+                chunk,
+                extended: false,
+                buf: Vec::new(),
+                done: false,
+                ops: VecDeque::new(),
+            }
+        }
+
+        /// Recognize the extended instruction set as well.
+        pub fn extended(mut self) -> Self {
+            self.extended = true;
+            self
+        }
+
+        fn fill(&mut self) -> io::Result<()> {
+            let start = self.buf.len();
+            self.buf.resize(start + self.chunk, 0);
+            let n = loop {
+                match self.reader.read(&mut self.buf[start..]) {
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                    Err(e) => {
+                        self.buf.truncate(start);
+                        return Err(e);
+                    }
+                    Ok(n) => break n,
+                }
+            };
+            self.buf.truncate(start + n);
+            self.done = n == 0;
+            for b in &mut self.buf[start..] {
+                if !b.is_ascii() {
+                    *b = 0;
+                }
+            }
+            Ok(())
+        }
+
+        /// Scan everything that can't be the start of an op running past
+        /// what's been read so far, or everything once the reader's done.
+        fn scan(&mut self) {
+            let s = std::str::from_utf8(&self.buf).unwrap();
+            let mut p = Parser::new(s);
+            p.extended = self.extended;
+            while !p.eof() && (self.done || s.len() - p.offset >= LOOKAHEAD) {
+                if let Some(op) = p.try_op() {
+                    self.ops.push_back(op);
+                } else {
+                    p.advance();
+                }
+            }
+            let offset = p.offset;
+            self.buf.drain(..offset);
+        }
+    }
+
+    impl<R: Read> Iterator for StreamParser<R> {
+        type Item = io::Result<Op>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                if let Some(op) = self.ops.pop_front() {
+                    return Some(Ok(op));
+                }
+                if self.done {
+                    return None;
+                }
+                if let Err(e) = self.fill() {
+                    return Some(Err(e));
+                }
+                self.scan();
+            }
+        }
+    }
+
     pub struct OpParser<'a> {
         p: Parser<'a>,
     }
@@ -217,4 +556,89 @@ mod parser {
             None
         }
     }
+    pub struct Listing<'a> {
+        p: Parser<'a>,
+    }
+
+    impl<'a> Iterator for Listing<'a> {
+        type Item = Listed<'a>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            while !self.p.eof() {
+                let (offset, line, column) = (self.p.offset, self.p.line, self.p.column);
+                if let Some(op) = self.p.op() {
+                    let text = &self.p.src[offset..self.p.offset];
+                    // Skip a char past a rejected op, as `OpParser` does.
+                    if op.is_err() {
+                        self.p.advance();
+                    }
+                    return Some(Listed {
+                        offset,
+                        line,
+                        column,
+                        text,
+                        op,
+                    });
+                }
+                self.p.advance();
+            }
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day03::{
+        op::Op,
+        parser::{Parser, Reject, StreamParser},
+    };
+
+    /// Whatever the chunk size, the same ops as scanning it all at once.
+    #[test]
+    fn test_stream() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))\
+            é→mul(123,456)mul(1234,5)jmp(-12)push()";
+        for extended in [false, true] {
+            let parser = |p: Parser<'static>| if extended { p.extended() } else { p };
+            let expected = Vec::from_iter(parser(Parser::new(input)).op_parser());
+            for chunk in 1..=40 {
+                let stream = StreamParser::new(input.as_bytes(), chunk);
+                let stream = if extended { stream.extended() } else { stream };
+                let ops: Vec<Op> = stream.map(|op| op.unwrap()).collect();
+                assert_eq!(ops, expected, "chunk size {}", chunk);
+            }
+        }
+        // Not UTF-8 at all.
+        let dump = b"\xffmul(1,\x802)mul(3,4)\xc3";
+        let ops: Vec<Op> = StreamParser::new(&dump[..], 3)
+            .map(|op| op.unwrap())
+            .collect();
+        assert_eq!(ops, [Op::Mul(3, 4)]);
+    }
+
+    #[test]
+    fn test_listing() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)un\ndo()?mul(8,5))mul(1234,5)";
+        let listed = Vec::from_iter(Parser::new(input).listing());
+        // The same ops as without positions.
+        assert_eq!(
+            Vec::from_iter(listed.iter().filter_map(|l| l.op.ok())),
+            Vec::from_iter(Parser::new(input).op_parser())
+        );
+        let first = &listed[0];
+        assert_eq!((first.offset, first.line, first.column), (1, 1, 2));
+        assert_eq!((first.text, first.op), ("mul(2,4)", Ok(Op::Mul(2, 4))));
+        let rejected = Vec::from_iter(
+            listed
+                .iter()
+                .filter_map(|l| l.op.err().map(|reject| (l.text, reject))),
+        );
+        assert_eq!(
+            rejected,
+            [("mul(32,64", Reject::Close), ("mul(1234", Reject::TooLong)]
+        );
+        let after = listed.iter().find(|l| l.op == Ok(Op::Do)).unwrap();
+        assert_eq!((after.line, after.column), (2, 1));
+    }
 }
@@ -0,0 +1,249 @@
+//! The same ops as `OpParser` finds, but straight from bytes, jumping from
+//! one byte that could start an op to the next instead of trying at each
+//! char.
+//!
+//! It matches the way `Parser` does, keeping whatever it matched of an op
+//! that didn't parse and then skipping one more byte, so that the two
+//! always agree. A char that isn't ASCII can't be any part of an op, so
+//! skipping it a byte at a time comes to the same thing as skipping it
+//! whole.
+
+use super::op::{Num, Op};
+
+pub struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    extended: bool,
+    /// Which bytes an op can start with.
+    starts: [bool; 256],
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self::with_starts(bytes, b"md")
+    }
+
+    /// Only the `mul`s, as `MulParser` finds them. That isn't quite what
+    /// `new` finds: there, `dmul(1,2)` is a `do` that didn't parse, taking
+    /// the `m` with it.
+    pub fn muls(bytes: &'a [u8]) -> Self {
+        Self::with_starts(bytes, b"m")
+    }
+
+    fn with_starts(bytes: &'a [u8], starts: &[u8]) -> Self {
+        let mut scanner = Self {
+            bytes,
+            pos: 0,
+            extended: false,
+            starts: [false; 256],
+        };
+        scanner.set_starts(starts);
+        scanner
+    }
+
+    /// Recognize the extended instruction set as well.
+    pub fn extended(mut self) -> Self {
+        self.extended = true;
+        self.set_starts(b"aspljo");
+        self
+    }
+
+    fn set_starts(&mut self, starts: &[u8]) {
+        for b in starts {
+            self.starts[*b as usize] = true;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn bmatch(&mut self, b: u8) -> bool {
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Like `Parser::smatch`, the part that matched stays matched.
+    fn smatch(&mut self, s: &[u8]) -> bool {
+        s.iter().all(|b| self.bmatch(*b))
+    }
+
+    fn digit(&mut self) -> Option<Num> {
+        let b = self.peek().filter(u8::is_ascii_digit)?;
+        self.pos += 1;
+        Some((b - b'0') as Num)
+    }
+
+    fn num(&mut self) -> Option<Num> {
+        let mut i = self.digit()?;
+        for _ in 0..2 {
+            if let Some(digit) = self.digit() {
+                i = i * 10 + digit;
+            }
+        }
+        if self.digit().is_some() {
+            None
+        } else {
+            Some(i)
+        }
+    }
+
+    /// num ',' num ')'
+    fn args(&mut self) -> Option<(Num, Num)> {
+        let left = self.num()?;
+        self.bmatch(b',').then_some(())?;
+        let right = self.num()?;
+        self.bmatch(b')').then_some((left, right))
+    }
+
+    /// num ')'
+    fn register(&mut self) -> Option<Num> {
+        let r = self.num()?;
+        self.bmatch(b')').then_some(r)
+    }
+
+    fn try_op(&mut self) -> Option<Op> {
+        match self.peek()? {
+            b'm' if self.smatch(b"mul(") => self.args().map(|(l, r)| Op::Mul(l, r)),
+            b'd' if self.smatch(b"do") => {
+                if self.bmatch(b'(') {
+                    self.bmatch(b')').then_some(Op::Do)
+                } else {
+                    self.smatch(b"n't()").then_some(Op::Dont)
+                }
+            }
+            _ if !self.extended => None,
+            b'a' if self.smatch(b"add(") => self.args().map(|(l, r)| Op::Add(l, r)),
+            b's' if self.smatch(b"s") => {
+                if self.smatch(b"ub(") {
+                    self.args().map(|(l, r)| Op::Sub(l, r))
+                } else if self.smatch(b"tore(") {
+                    self.register().map(Op::Store)
+                } else {
+                    None
+                }
+            }
+            b'l' if self.smatch(b"load(") => self.register().map(Op::Load),
+            b'p' if self.smatch(b"p") => {
+                if self.smatch(b"ush()") {
+                    Some(Op::Push)
+                } else {
+                    self.smatch(b"op()").then_some(Op::Pop)
+                }
+            }
+            b'j' if self.smatch(b"jmp(") => {
+                let negative = self.bmatch(b'-');
+                let offset = self.num()? as i16;
+                self.bmatch(b')')
+                    .then_some(Op::Jmp(if negative { -offset } else { offset }))
+            }
+            b'o' if self.smatch(b"out()") => Some(Op::Out),
+            _ => None,
+        }
+    }
+}
+
+impl Iterator for Scanner<'_> {
+    type Item = Op;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rest = &self.bytes[self.pos..];
+            self.pos += rest
+                .iter()
+                .position(|b| self.starts[*b as usize])
+                .unwrap_or(rest.len());
+            if self.pos == self.bytes.len() {
+                return None;
+            }
+            if let Some(op) = self.try_op() {
+                return Some(op);
+            }
+            self.pos = (self.pos + 1).min(self.bytes.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::day03::{op::Op, parser::Parser};
+
+    use super::Scanner;
+
+    /// The puzzle input comes out the same both ways.
+    #[test]
+    fn test_input() {
+        let input = fs::read_to_string("day03_input.txt").unwrap();
+        assert_eq!(
+            Vec::from_iter(Scanner::muls(input.as_bytes())),
+            Vec::from_iter(Parser::new(&input).mul_parser().map(|(l, r)| Op::Mul(l, r)))
+        );
+        assert_eq!(
+            Vec::from_iter(Scanner::new(input.as_bytes())),
+            Vec::from_iter(Parser::new(&input).op_parser())
+        );
+    }
+
+    #[test]
+    fn test_muls() {
+        let input = "dmul(1,2)don't()mul(3,4)mmul(5,6)mul(7,8";
+        assert_eq!(
+            Vec::from_iter(Scanner::muls(input.as_bytes())),
+            Vec::from_iter(Parser::new(input).mul_parser().map(|(l, r)| Op::Mul(l, r)))
+        );
+        assert_eq!(
+            Vec::from_iter(Scanner::muls(input.as_bytes())),
+            [Op::Mul(1, 2), Op::Mul(3, 4)]
+        );
+    }
+
+    /// Agrees with `OpParser` on near misses, including ones that start
+    /// inside the one before.
+    #[test]
+    fn test_agrees() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))\
+            mmul(1,2)mul(mul(3,4)do(don't(mul(1234,5)mul(1,2345)mul(7,8)mu\u{e9}l(1,1)\
+            \u{e9}mul(9,9)add(1,2)ppop()pusjmp(-12)jmp(3out()sub(4,store(3)sstore(4)load(1)\
+            load(12,store()stub(1,2)";
+        assert_eq!(
+            Vec::from_iter(Scanner::new(input.as_bytes())),
+            Vec::from_iter(Parser::new(input).op_parser())
+        );
+        assert_eq!(
+            Vec::from_iter(Scanner::new(input.as_bytes()).extended()),
+            Vec::from_iter(Parser::new(input).extended().op_parser())
+        );
+    }
+
+    /// Random near misses, each scanned both ways.
+    #[test]
+    fn test_random() {
+        let pieces = [
+            "mul(", "do()", "don't()", "m", "d", "(", ")", ",", "1", "23", "456", "7890", "x",
+            "\u{e9}", "add(", "jmp(", "-", "push()", "pop()", "out()", "p", "s", "sub(", "store(",
+            "load(",
+        ];
+        let mut seed: u64 = 0x2024_0003;
+        for _ in 0..200 {
+            let mut input = String::new();
+            for _ in 0..60 {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                input += pieces[(seed % pieces.len() as u64) as usize];
+            }
+            assert_eq!(
+                Vec::from_iter(Scanner::new(input.as_bytes()).extended()),
+                Vec::from_iter(Parser::new(&input).extended().op_parser()),
+                "{}",
+                input
+            );
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! The extended instructions compiled to bytecode, and a machine to run it.
+//!
+//! Each instruction is an opcode byte followed by its operands, little
+//! endian. `jmp` counts in instructions, not bytes, so the program keeps
+//! a table of where each one starts.
+//!
+//! The machine has an accumulator that `mul`, `add` and `sub` add their
+//! result to, a stack for `push()` and `pop()`, registers `0` to `7` for
+//! `store(r)` to copy the accumulator into and `load(r)` to copy it back
+//! out of, and an output that `out()` appends the accumulator to. After
+//! `don't()`, everything but `do()` is skipped, jumps included.
+
+use std::fmt;
+
+use super::op::{Num, Op};
+
+const MUL: u8 = 0x01;
+const DO: u8 = 0x02;
+const DONT: u8 = 0x03;
+const ADD: u8 = 0x04;
+const SUB: u8 = 0x05;
+const PUSH: u8 = 0x06;
+const POP: u8 = 0x07;
+const JMP: u8 = 0x08;
+const OUT: u8 = 0x09;
+const STORE: u8 = 0x0a;
+const LOAD: u8 = 0x0b;
+
+pub const REGISTERS: usize = 8;
+
+pub struct Program {
+    code: Vec<u8>,
+    /// Where each instruction starts in `code`.
+    starts: Vec<usize>,
+}
+
+impl Program {
+    pub fn compile<I>(ops: I) -> Self
+    where
+        I: IntoIterator<Item = Op>,
+    {
+        let mut code: Vec<u8> = Vec::new();
+        let mut starts: Vec<usize> = Vec::new();
+        for op in ops {
+            starts.push(code.len());
+            let (opcode, operands): (u8, &[u16]) = match op {
+                Op::Mul(l, r) => (MUL, &[l, r]),
+                Op::Do => (DO, &[]),
+                Op::Dont => (DONT, &[]),
+                Op::Add(l, r) => (ADD, &[l, r]),
+                Op::Sub(l, r) => (SUB, &[l, r]),
+                Op::Push => (PUSH, &[]),
+                Op::Pop => (POP, &[]),
+                Op::Jmp(n) => (JMP, &[n as u16]),
+                Op::Out => (OUT, &[]),
+                Op::Store(r) => (STORE, &[r]),
+                Op::Load(r) => (LOAD, &[r]),
+            };
+            code.push(opcode);
+            for operand in operands {
+                code.extend(operand.to_le_bytes());
+            }
+        }
+        Self { code, starts }
+    }
+
+    fn operand(&self, at: usize) -> u16 {
+        u16::from_le_bytes([self.code[at], self.code[at + 1]])
+    }
+
+    /// The instruction at index `pc`.
+    fn decode(&self, pc: usize) -> Op {
+        let at = self.starts[pc];
+        let arg = |i: usize| -> Num { self.operand(at + 1 + 2 * i) };
+        match self.code[at] {
+            MUL => Op::Mul(arg(0), arg(1)),
+            DO => Op::Do,
+            DONT => Op::Dont,
+            ADD => Op::Add(arg(0), arg(1)),
+            SUB => Op::Sub(arg(0), arg(1)),
+            PUSH => Op::Push,
+            POP => Op::Pop,
+            JMP => Op::Jmp(arg(0) as i16),
+            OUT => Op::Out,
+            STORE => Op::Store(arg(0)),
+            LOAD => Op::Load(arg(0)),
+            opcode => panic!("bad opcode {:#04x} at byte {}", opcode, at),
+        }
+    }
+}
+
+/// Why the machine stopped.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Halt {
+    /// Ran or jumped off either end of the program.
+    End,
+    /// Took as many steps as it was allowed.
+    StepLimit,
+    /// `pop()` with nothing pushed, at this instruction.
+    EmptyStack(usize),
+    /// The accumulator overflowed at this instruction.
+    Overflow(usize),
+    /// `store` or `load` with a register past the last, at this
+    /// instruction.
+    BadRegister(usize),
+}
+
+impl fmt::Display for Halt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Halt::End => write!(f, "end of program"),
+            Halt::StepLimit => write!(f, "step limit"),
+            Halt::EmptyStack(pc) => write!(f, "empty stack at instruction {}", pc),
+            Halt::Overflow(pc) => write!(f, "overflow at instruction {}", pc),
+            Halt::BadRegister(pc) => write!(f, "no such register at instruction {}", pc),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Outcome {
+    pub acc: i64,
+    pub registers: [i64; REGISTERS],
+    pub out: Vec<i64>,
+    pub steps: usize,
+    pub halt: Halt,
+}
+
+/// Run `program` from the start for at most `step_limit` instructions.
+pub fn run(program: &Program, step_limit: usize) -> Outcome {
+    let mut acc: i64 = 0;
+    let mut registers = [0i64; REGISTERS];
+    let mut enabled = true;
+    let mut pc: usize = 0;
+    let mut stack: Vec<i64> = Vec::new();
+    let mut out: Vec<i64> = Vec::new();
+    let mut steps: usize = 0;
+    let halt = loop {
+        if pc >= program.starts.len() {
+            break Halt::End;
+        }
+        if steps == step_limit {
+            break Halt::StepLimit;
+        }
+        steps += 1;
+        let op = program.decode(pc);
+        let mut next = pc as i64 + 1;
+        match op {
+            Op::Do => enabled = true,
+            _ if !enabled => (),
+            Op::Dont => enabled = false,
+            Op::Mul(l, r) | Op::Add(l, r) | Op::Sub(l, r) => {
+                let (l, r) = (l as i64, r as i64);
+                let value = match op {
+                    Op::Mul(..) => l * r,
+                    Op::Add(..) => l + r,
+                    _ => l - r,
+                };
+                match acc.checked_add(value) {
+                    Some(sum) => acc = sum,
+                    None => break Halt::Overflow(pc),
+                }
+            }
+            Op::Push => stack.push(acc),
+            Op::Pop => match stack.pop() {
+                Some(value) => acc = value,
+                None => break Halt::EmptyStack(pc),
+            },
+            Op::Jmp(n) => next = pc as i64 + n as i64,
+            Op::Out => out.push(acc),
+            Op::Store(r) => match registers.get_mut(r as usize) {
+                Some(register) => *register = acc,
+                None => break Halt::BadRegister(pc),
+            },
+            Op::Load(r) => match registers.get(r as usize) {
+                Some(register) => acc = *register,
+                None => break Halt::BadRegister(pc),
+            },
+        }
+        match usize::try_from(next) {
+            Ok(next) => pc = next,
+            Err(_) => break Halt::End,
+        }
+    };
+    Outcome {
+        acc,
+        registers,
+        out,
+        steps,
+        halt,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day03::{
+        op::{interp, Op},
+        parser::Parser,
+    };
+
+    use super::{run, Halt, Program};
+
+    fn run_str(s: &str, step_limit: usize) -> super::Outcome {
+        run(
+            &Program::compile(Parser::new(s).extended().op_parser()),
+            step_limit,
+        )
+    }
+
+    /// With only the puzzle's instructions, the machine agrees with part 2.
+    #[test]
+    fn test_base() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let outcome = run_str(input, 1000);
+        assert_eq!(outcome.acc as u64, interp(Parser::new(input).op_parser()));
+        assert_eq!(outcome.acc, 48);
+        assert_eq!(outcome.halt, Halt::End);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let ops = [
+            Op::Mul(999, 1),
+            Op::Do,
+            Op::Dont,
+            Op::Add(0, 12),
+            Op::Sub(7, 8),
+            Op::Push,
+            Op::Pop,
+            Op::Jmp(-3),
+            Op::Out,
+            Op::Store(7),
+            Op::Load(0),
+        ];
+        let program = Program::compile(ops);
+        assert_eq!(program.code.len(), 11 + 3 * 4 + 2 * 3);
+        assert_eq!(
+            Vec::from_iter((0..ops.len()).map(|pc| program.decode(pc))),
+            ops
+        );
+    }
+
+    #[test]
+    fn test_extended() {
+        // Extended instructions are only seen when asked for.
+        assert_eq!(Parser::new("add(1,2)out()").op_parser().count(), 0);
+        let outcome = run_str("add(5,3)out()push()sub(1,4)out()pop()out()", 100);
+        assert_eq!(outcome.out, [8, 5, 8]);
+        assert_eq!(outcome.acc, 8);
+        // Jumps over the `out()`, and a `don't()` stops the jump too.
+        let outcome = run_str("jmp(2)out()add(1,1)don't()jmp(-3)do()out()", 100);
+        assert_eq!(outcome.out, [2]);
+        assert_eq!(outcome.steps, 6);
+    }
+
+    #[test]
+    fn test_registers() {
+        let outcome = run_str(
+            "add(2,3)store(1)mul(2,2)store(7)load(1)out()load(7)out()load(0)",
+            100,
+        );
+        assert_eq!(outcome.out, [5, 9]);
+        assert_eq!(outcome.acc, 0);
+        assert_eq!(outcome.registers, [0, 5, 0, 0, 0, 0, 0, 9]);
+        assert_eq!(run_str("store(8)", 10).halt, Halt::BadRegister(0));
+        assert_eq!(run_str("add(1,1)load(12)", 10).halt, Halt::BadRegister(1));
+    }
+
+    #[test]
+    fn test_halt() {
+        let outcome = run_str("add(1,0)out()jmp(-1)", 10);
+        assert_eq!(outcome.halt, Halt::StepLimit);
+        assert_eq!(outcome.out, [1; 5]);
+        assert_eq!(outcome.steps, 10);
+        assert_eq!(run_str("push()pop()pop()", 10).halt, Halt::EmptyStack(2));
+        assert_eq!(run_str("jmp(-5)", 10).halt, Halt::End);
+        assert_eq!(run_str("jmp(5)out()", 10).out, []);
+    }
+}
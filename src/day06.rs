@@ -1,15 +1,224 @@
-use std::fs;
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    iter::zip,
+    path::PathBuf,
+};
 
 use stuck::find_stucks;
 
-pub fn day06() {
+pub use dir::{Dir, TurnRule};
+pub use generate::{generate, search, Size};
+pub use guard::Guard;
+pub use heatmap::Heatmap;
+pub use map::{load_guards, Map, Pos, Square};
+pub use session::PatrolSession;
+
+#[derive(clap::Args)]
+#[group(id = "day06")]
+pub struct Options {
+    /// How the guard turns on hitting an obstacle: right, left, reverse,
+    /// alternate, or a sequence of turns such as RRL (B turns back)
+    #[arg(long, default_value = "right")]
+    pub turn: TurnRule,
+
+    /// Write a greyscale PGM image of how often each square was visited
+    #[arg(long, value_name = "PATH")]
+    pub heatmap_pgm: Option<PathBuf>,
+
+    /// Write a PPM image of when each square was first visited
+    #[arg(long, value_name = "PATH")]
+    pub heatmap_ppm: Option<PathBuf>,
+
+    /// Write per-square visit statistics as CSV
+    #[arg(long, value_name = "PATH")]
+    pub heatmap_csv: Option<PathBuf>,
+
+    /// Print a random map of this size instead of solving the input
+    #[arg(long, value_name = "WIDTHxHEIGHT")]
+    pub generate: Option<Size>,
+
+    /// Chance of each generated square being an obstacle
+    #[arg(long, default_value_t = 0.02)]
+    pub density: f64,
+
+    /// Seed for the generated map
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+
+    /// Keep trying seeds until the map has this many possible stucks
+    #[arg(long, value_name = "N")]
+    pub target_stucks: Option<usize>,
+
+    /// How many seeds to try when searching for --target-stucks
+    #[arg(long, default_value_t = 1000)]
+    pub attempts: usize,
+}
+
+pub fn day06(options: &Options) {
+    if let Some(size) = options.generate {
+        print_generated(options, size);
+        return;
+    }
+
     let input = fs::read_to_string("day06_input.txt").unwrap();
-    let (map, guard) = map::load_map(&input);
-    let visited_squares = guard.clone().find_visited(&map).unwrap();
-    println!("visited squares: {}", visited_squares.len());
+    let (map, guards) = map::load_guards(&input);
+    let guards = Vec::from_iter(
+        guards
+            .into_iter()
+            .map(|g| g.with_rule(options.turn.clone())),
+    );
+    let traces = Vec::from_iter(guards.iter().map(|g| g.clone().trace(&map)));
+    for (i, (guard, trace)) in zip(&guards, &traces).enumerate() {
+        if trace.is_loop() {
+            let pos = guard.pos.unwrap();
+            println!("guard {} at ({}, {}) is stuck in a loop", i, pos.x, pos.y);
+        } else {
+            let visited_squares = trace.visited();
+            println!("visited squares: {}", visited_squares.len());
+
+            let loops = find_stucks(&map, guard, &visited_squares);
+            println!("possible stucks: {}", loops);
+        }
+    }
+
+    if options.heatmap_pgm.is_some()
+        || options.heatmap_ppm.is_some()
+        || options.heatmap_csv.is_some()
+    {
+        write_heatmaps(options, &map, &guards);
+    }
+
+    for i in 0..traces.len() {
+        for j in (i + 1)..traces.len() {
+            let shared = patrol::intersection(&traces[i], &traces[j]);
+            println!("guards {} and {} share {} squares", i, j, shared.len());
+            if let Some((step, pos)) = patrol::first_collision(&traces[i], &traces[j]) {
+                println!(
+                    "guards {} and {} collide at ({}, {}) on step {}",
+                    i, j, pos.x, pos.y, step
+                );
+            }
+        }
+    }
+}
+
+fn print_generated(options: &Options, size: Size) {
+    if let Some(target) = options.target_stucks {
+        if let Some((seed, map)) = search(
+            size,
+            options.density,
+            options.seed,
+            target,
+            options.attempts,
+        ) {
+            eprintln!("seed: {}", seed);
+            print!("{}", map);
+        } else {
+            eprintln!("no map found in {} attempts", options.attempts);
+        }
+    } else {
+        print!("{}", generate(size, options.density, options.seed));
+    }
+}
+
+fn write_heatmaps(options: &Options, map: &Map, guards: &[Guard]) {
+    let mut heatmap = Heatmap::new(map);
+    for guard in guards {
+        heatmap.survey(guard.clone());
+    }
+    if let Some(path) = &options.heatmap_pgm {
+        heatmap
+            .write_pgm(&mut BufWriter::new(File::create(path).unwrap()))
+            .unwrap();
+    }
+    if let Some(path) = &options.heatmap_ppm {
+        heatmap
+            .write_ppm(&mut BufWriter::new(File::create(path).unwrap()))
+            .unwrap();
+    }
+    if let Some(path) = &options.heatmap_csv {
+        heatmap
+            .write_csv(&mut BufWriter::new(File::create(path).unwrap()))
+            .unwrap();
+    }
+}
+
+mod session;
+
+mod heatmap;
+
+mod generate;
+
+mod patrol {
+    use std::collections::HashSet;
+
+    use super::map::Pos;
+
+    /// The route a guard takes, one position per time step.
+    /// If the guard loops, the path from `cycle_start` onwards repeats
+    /// forever.
+    pub struct Trace {
+        pub path: Vec<Pos>,
+        pub cycle_start: Option<usize>,
+    }
+
+    impl Trace {
+        /// Where the guard is on a given step, or `None` if it has left the map.
+        pub fn at(&self, step: usize) -> Option<Pos> {
+            if step < self.path.len() {
+                Some(self.path[step])
+            } else {
+                let start = self.cycle_start?;
+                let period = self.path.len() - start;
+                Some(self.path[start + (step - start) % period])
+            }
+        }
+
+        pub fn is_loop(&self) -> bool {
+            self.cycle_start.is_some()
+        }
+
+        pub fn visited(&self) -> HashSet<Pos> {
+            HashSet::from_iter(self.path.iter().copied())
+        }
+
+        fn period(&self) -> Option<usize> {
+            self.cycle_start.map(|start| self.path.len() - start)
+        }
+    }
 
-    let loops = find_stucks(&map, &guard, &visited_squares);
-    println!("possible stucks: {}", loops);
+    /// Squares visited by both guards, at any time.
+    pub fn intersection(a: &Trace, b: &Trace) -> HashSet<Pos> {
+        let b = b.visited();
+        HashSet::from_iter(a.path.iter().copied().filter(|pos| b.contains(pos)))
+    }
+
+    /// The first step on which both guards stand on the same square.
+    pub fn first_collision(a: &Trace, b: &Trace) -> Option<(usize, Pos)> {
+        // Past this many steps, the pair of positions starts repeating (or a
+        // guard has left the map), so nothing new can happen.
+        let horizon = match (a.period(), b.period()) {
+            (Some(pa), Some(pb)) => {
+                a.cycle_start.unwrap().max(b.cycle_start.unwrap()) + pa / gcd(pa, pb) * pb
+            }
+            (Some(_), None) => b.path.len(),
+            (None, Some(_)) => a.path.len(),
+            (None, None) => a.path.len().min(b.path.len()),
+        };
+        (0..horizon).find_map(|step| match (a.at(step), b.at(step)) {
+            (Some(pa), Some(pb)) if pa == pb => Some((step, pa)),
+            _ => None,
+        })
+    }
+
+    fn gcd(a: usize, b: usize) -> usize {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
 }
 
 mod stuck {
@@ -19,6 +228,7 @@ mod stuck {
         dir::ALL_DIRS,
         guard::Guard,
         map::{Map, Pos, Square, TryAdd},
+        session::PatrolSession,
     };
 
     fn stuckable(map: &Map, guard: &Guard, initial_visited: &HashSet<Pos>) -> HashSet<Pos> {
@@ -38,31 +248,51 @@ mod stuck {
 
     pub fn find_stucks(map: &Map, guard: &Guard, initial_visited: &HashSet<Pos>) -> usize {
         let mut loops: usize = 0;
+        let mut session = PatrolSession::new(map.clone(), guard.clone());
         for pos in stuckable(map, guard, initial_visited) {
-            let mut new_map = map.clone();
-            new_map.put(pos, Square::Obstacle);
-            if guard.clone().find_visited(&new_map).is_none() {
+            session.place(pos);
+            if session.is_loop() {
                 loops += 1;
             }
+            session.remove(pos);
         }
         loops
     }
 }
 
 mod guard {
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
 
     use crate::day06::map::TryAdd;
 
     use super::{
-        dir::Dir,
+        dir::{Dir, TurnRule},
         map::{Map, Pos, Square},
+        patrol::Trace,
     };
 
+    /// Everything that decides where a guard goes next. Seeing the same state
+    /// twice means the guard is going around in circles.
+    #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct State {
+        pub pos: Pos,
+        pub dir: Dir,
+        turns: usize,
+        wait: u8,
+        warp: bool,
+    }
+
     #[derive(Clone)]
     pub struct Guard {
         pub pos: Option<Pos>,
         dir: Dir,
+        rule: TurnRule,
+        /// Obstacles hit so far, modulo the rule's period.
+        turns: usize,
+        /// Steps left before leaving a slow square.
+        wait: u8,
+        /// Standing on a teleporter that hasn't fired yet.
+        warp: bool,
     }
 
     impl Guard {
@@ -70,16 +300,83 @@ mod guard {
             Self {
                 pos: Some(pos),
                 dir,
+                rule: TurnRule::Right,
+                turns: 0,
+                wait: 0,
+                warp: false,
+            }
+        }
+
+        pub fn with_rule(self, rule: TurnRule) -> Self {
+            Self {
+                rule,
+                turns: 0,
+                ..self
+            }
+        }
+
+        pub fn state(&self) -> Option<State> {
+            Some(State {
+                pos: self.pos?,
+                dir: self.dir,
+                turns: self.turns,
+                wait: self.wait,
+                warp: self.warp,
+            })
+        }
+
+        /// Put the guard back the way it was when `state` was taken.
+        pub fn restore(&mut self, state: State) {
+            self.pos = Some(state.pos);
+            self.dir = state.dir;
+            self.turns = state.turns;
+            self.wait = state.wait;
+            self.warp = state.warp;
+        }
+
+        /// The square the next step looks at, if any. Changing any other
+        /// square can't change what the next step does.
+        pub fn probe(&self) -> Option<Pos> {
+            if self.wait > 0 || self.warp {
+                None
+            } else {
+                self.pos?.try_add(self.dir)
             }
         }
 
+        fn bump(&mut self) {
+            self.dir = self.dir.turn(self.rule.turn(self.turns));
+            self.turns = (self.turns + 1) % self.rule.period();
+        }
+
         pub fn step(&mut self, map: &Map) {
             let old_pos = self.pos.unwrap();
-            if let Some(new_pos) = old_pos.try_add(self.dir) {
-                debug_assert_eq!(map.get(old_pos), Some(Square::Empty));
+            if self.wait > 0 {
+                self.wait -= 1;
+            } else if self.warp {
+                self.warp = false;
+                self.pos = Some(map.partner(old_pos));
+            } else if let Some(new_pos) = old_pos.try_add(self.dir) {
+                debug_assert_ne!(map.get(old_pos), Some(Square::Obstacle));
                 match map.get(new_pos) {
                     Some(Square::Empty) => self.pos = Some(new_pos),
-                    Some(Square::Obstacle) => self.dir = self.dir.rotate(),
+                    Some(Square::Obstacle) => self.bump(),
+                    Some(Square::OneWay(dir)) => {
+                        if dir == self.dir.reverse() {
+                            self.bump();
+                        } else {
+                            self.pos = Some(new_pos);
+                            self.dir = dir;
+                        }
+                    }
+                    Some(Square::Teleport(_)) => {
+                        self.pos = Some(new_pos);
+                        self.warp = true;
+                    }
+                    Some(Square::Slow(cost)) => {
+                        self.pos = Some(new_pos);
+                        self.wait = cost;
+                    }
                     None => self.pos = None,
                 }
             } else {
@@ -89,21 +386,43 @@ mod guard {
 
         pub fn find_visited(&mut self, map: &Map) -> Option<HashSet<Pos>> {
             let mut visited: HashSet<Pos> = HashSet::new();
-            let mut visited_dir: HashSet<(Pos, Dir)> = HashSet::new();
-            while let Some(cur_pos) = self.pos {
-                if !visited_dir.insert((cur_pos, self.dir)) {
+            let mut visited_states: HashSet<State> = HashSet::new();
+            while let Some(state) = self.state() {
+                if !visited_states.insert(state) {
                     return None;
                 }
-                visited.insert(cur_pos);
+                visited.insert(state.pos);
                 self.step(map);
             }
             Some(visited)
         }
+
+        pub fn trace(&mut self, map: &Map) -> Trace {
+            let mut path: Vec<Pos> = Vec::new();
+            let mut seen: HashMap<State, usize> = HashMap::new();
+            while let Some(state) = self.state() {
+                if let Some(&step) = seen.get(&state) {
+                    return Trace {
+                        path,
+                        cycle_start: Some(step),
+                    };
+                }
+                seen.insert(state, path.len());
+                path.push(state.pos);
+                self.step(map);
+            }
+            Trace {
+                path,
+                cycle_start: None,
+            }
+        }
     }
 }
 
 mod dir {
-    #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+    use std::str::FromStr;
+
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
     pub enum Dir {
         N,
         S,
@@ -114,6 +433,28 @@ mod dir {
     pub const ALL_DIRS: [Dir; 4] = [Dir::N, Dir::S, Dir::E, Dir::W];
 
     impl Dir {
+        /// The direction a guard drawn as `c` is facing.
+        pub fn from_guard(c: char) -> Option<Self> {
+            match c {
+                '^' => Some(Dir::N),
+                'v' => Some(Dir::S),
+                '>' => Some(Dir::E),
+                '<' => Some(Dir::W),
+                _ => None,
+            }
+        }
+
+        /// The direction a one-way square drawn as `c` points.
+        pub fn from_arrow(c: char) -> Option<Self> {
+            match c {
+                'n' => Some(Dir::N),
+                's' => Some(Dir::S),
+                'e' => Some(Dir::E),
+                'w' => Some(Dir::W),
+                _ => None,
+            }
+        }
+
         pub fn vector(self) -> (i8, i8) {
             match self {
                 Dir::N => (0, -1),
@@ -131,15 +472,94 @@ mod dir {
                 Dir::S => Dir::W,
             }
         }
+
+        pub fn reverse(self) -> Self {
+            self.rotate().rotate()
+        }
+
+        pub fn turn(self, turn: Turn) -> Self {
+            match turn {
+                Turn::Right => self.rotate(),
+                Turn::Left => self.reverse().rotate(),
+                Turn::Back => self.reverse(),
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum Turn {
+        Right,
+        Left,
+        Back,
+    }
+
+    /// What a guard does each time it bumps into an obstacle.
+    #[derive(Clone, PartialEq, Debug)]
+    pub enum TurnRule {
+        Right,
+        Left,
+        Reverse,
+        /// Left, then right, then left again...
+        Alternate,
+        /// Work through the turns in order, starting over after the last.
+        Sequence(Vec<Turn>),
+    }
+
+    impl TurnRule {
+        /// How many obstacles it takes before the rule starts over.
+        pub fn period(&self) -> usize {
+            match self {
+                TurnRule::Right | TurnRule::Left | TurnRule::Reverse => 1,
+                TurnRule::Alternate => 2,
+                TurnRule::Sequence(turns) => turns.len(),
+            }
+        }
+
+        /// The turn to take on hitting obstacle number `n`, counting from 0.
+        pub fn turn(&self, n: usize) -> Turn {
+            match self {
+                TurnRule::Right => Turn::Right,
+                TurnRule::Left => Turn::Left,
+                TurnRule::Reverse => Turn::Back,
+                TurnRule::Alternate => [Turn::Left, Turn::Right][n % 2],
+                TurnRule::Sequence(turns) => turns[n % turns.len()],
+            }
+        }
+    }
+
+    impl FromStr for TurnRule {
+        type Err = String;
+
+        /// Either the name of a rule, or a sequence of turns such as `RRL`,
+        /// where `R` is right, `L` is left and `B` is back.
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "right" => Ok(TurnRule::Right),
+                "left" => Ok(TurnRule::Left),
+                "reverse" => Ok(TurnRule::Reverse),
+                "alternate" => Ok(TurnRule::Alternate),
+                "" => Err("empty turn sequence".to_string()),
+                _ => s
+                    .chars()
+                    .map(|c| match c {
+                        'R' => Ok(Turn::Right),
+                        'L' => Ok(Turn::Left),
+                        'B' => Ok(Turn::Back),
+                        _ => Err(format!("unknown turn '{}'", c)),
+                    })
+                    .collect::<Result<Vec<Turn>, String>>()
+                    .map(TurnRule::Sequence),
+            }
+        }
     }
 }
 
 mod map {
-    use std::ops;
+    use std::{collections::HashMap, ops};
 
     use super::{dir::Dir, guard::Guard};
 
-    #[derive(Copy, Clone, PartialEq, Eq, Hash)]
+    #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
     pub struct Pos {
         pub x: u16,
         pub y: u16,
@@ -191,12 +611,20 @@ mod map {
     pub enum Square {
         Empty,
         Obstacle,
+        /// Can't be entered heading the opposite way, and points the guard
+        /// along the arrow otherwise.
+        OneWay(Dir),
+        /// Sends the guard to the other teleporter with the same letter.
+        Teleport(char),
+        /// Holds the guard up for this many extra steps.
+        Slow(u8),
     }
 
     #[derive(Clone)]
     pub struct Map {
         pub width: u16,
         elems: Vec<Square>,
+        links: HashMap<Pos, Pos>,
     }
 
     impl Map {
@@ -206,53 +634,83 @@ mod map {
 
         pub fn get(&self, pos: Pos) -> Option<Square> {
             if pos.x < self.width && pos.y < (self.height() as u16) {
-                Some(self.elems[(pos.y as usize) * self.height() + (pos.x as usize)])
+                Some(self.elems[(pos.y as usize) * (self.width as usize) + (pos.x as usize)])
             } else {
                 None
             }
         }
 
+        /// The teleporter paired with the one at `pos`.
+        pub fn partner(&self, pos: Pos) -> Pos {
+            self.links[&pos]
+        }
+
         pub fn put(&mut self, pos: Pos, square: Square) {
-            let height = self.height();
-            if pos.x < self.width && pos.y < (height as u16) {
-                self.elems[(pos.y as usize) * height + (pos.x as usize)] = square;
+            if pos.x < self.width && pos.y < (self.height() as u16) {
+                self.elems[(pos.y as usize) * (self.width as usize) + (pos.x as usize)] = square;
             } else {
                 panic!("bad coords")
             }
         }
     }
 
-    pub fn load_map(s: &str) -> (Map, Guard) {
-        let mut guard: Option<Guard> = None;
+    /// Load a map with any number of guards, each drawn as `^`, `>`, `v` or
+    /// `<` depending on which way it faces.
+    ///
+    /// Besides `.` and `#`, squares may be one-way arrows `n`, `e`, `s` or
+    /// `w`, teleporters drawn as a capital letter appearing exactly twice, or
+    /// slow squares `1` to `9` costing that many extra steps.
+    pub fn load_guards(s: &str) -> (Map, Vec<Guard>) {
+        let mut guards: Vec<Guard> = Vec::new();
+        let mut teleports: HashMap<char, Vec<Pos>> = HashMap::new();
         let width = s.lines().next().unwrap().chars().count();
         let mut elems: Vec<Square> = Vec::with_capacity(s.lines().size_hint().0 * width);
         for (y, line) in s.lines().enumerate() {
             assert_eq!(line.chars().count(), width);
             for (x, c) in line.chars().enumerate() {
+                let pos = Pos {
+                    x: x as u16,
+                    y: y as u16,
+                };
                 match c {
                     '.' => elems.push(Square::Empty),
                     '#' => elems.push(Square::Obstacle),
-                    '^' => {
-                        assert!(guard.is_none());
-                        guard = Some(Guard::new(
-                            Pos {
-                                x: x as u16,
-                                y: y as u16,
-                            },
-                            Dir::N,
-                        ));
-                        elems.push(Square::Empty);
+                    'A'..='Z' => {
+                        teleports.entry(c).or_default().push(pos);
+                        elems.push(Square::Teleport(c));
+                    }
+                    '1'..='9' => elems.push(Square::Slow(c.to_digit(10).unwrap() as u8)),
+                    _ => {
+                        if let Some(dir) = Dir::from_guard(c) {
+                            guards.push(Guard::new(pos, dir));
+                            elems.push(Square::Empty);
+                        } else if let Some(dir) = Dir::from_arrow(c) {
+                            elems.push(Square::OneWay(dir));
+                        } else {
+                            panic!("unexpected character '{}'", c)
+                        }
                     }
-                    _ => panic!("unexpected character '{}'", c),
                 }
             }
         }
+        let mut links: HashMap<Pos, Pos> = HashMap::new();
+        for (c, ends) in teleports {
+            assert_eq!(
+                ends.len(),
+                2,
+                "teleporter '{}' needs exactly one partner",
+                c
+            );
+            links.insert(ends[0], ends[1]);
+            links.insert(ends[1], ends[0]);
+        }
         (
             Map {
                 width: width as u16,
                 elems,
+                links,
             },
-            guard.unwrap(),
+            guards,
         )
     }
 }
@@ -260,15 +718,15 @@ mod map {
 #[cfg(test)]
 mod tests {
     use crate::day06::{
+        dir::{Turn, TurnRule},
         find_stucks,
         map::{Pos, Square},
+        patrol::{first_collision, intersection},
     };
 
-    use super::map::load_map;
+    use super::map::load_guards;
 
-    #[test]
-    fn test_input() {
-        let input = "....#.....
+    const SAMPLE: &str = "....#.....
 .........#
 ..........
 ..#.......
@@ -279,11 +737,104 @@ mod tests {
 #.........
 ......#...
 ";
-        let (map, guard) = load_map(input);
+
+    #[test]
+    fn test_input() {
+        let (map, guards) = load_guards(SAMPLE);
+        assert_eq!(guards.len(), 1);
+        let guard = &guards[0];
         assert_eq!(map.get(Pos { x: 9, y: 1 }), Some(Square::Obstacle));
         let visited = guard.clone().find_visited(&map).unwrap();
         assert_eq!(visited.len(), 41);
-        let stucks = find_stucks(&map, &guard, &visited);
+        let stucks = find_stucks(&map, guard, &visited);
         assert_eq!(stucks, 6);
     }
+
+    #[test]
+    fn test_guards() {
+        let input = ".....
+>...<
+.....
+";
+        let (map, guards) = load_guards(input);
+        assert_eq!(guards.len(), 2);
+        let a = guards[0].clone().trace(&map);
+        let b = guards[1].clone().trace(&map);
+        assert!(!a.is_loop());
+        assert_eq!(a.visited().len(), 5);
+        assert_eq!(intersection(&a, &b).len(), 5);
+        assert_eq!(first_collision(&a, &b), Some((2, Pos { x: 2, y: 1 })));
+
+        let input = ".#...
+....#
+.^...
+#...<
+...#.
+";
+        let (map, guards) = load_guards(input);
+        let a = guards[0].clone().trace(&map);
+        let b = guards[1].clone().trace(&map);
+        assert!(a.is_loop());
+        assert!(b.is_loop());
+        assert_eq!(a.visited().len(), 8);
+        assert_eq!(b.visited().len(), 9);
+        assert_eq!(intersection(&a, &b), a.visited());
+        assert_eq!(first_collision(&a, &b), None);
+    }
+
+    #[test]
+    fn test_rules() {
+        assert_eq!("left".parse(), Ok(TurnRule::Left));
+        assert_eq!(
+            "RLB".parse(),
+            Ok(TurnRule::Sequence(vec![
+                Turn::Right,
+                Turn::Left,
+                Turn::Back
+            ]))
+        );
+        assert!("RX".parse::<TurnRule>().is_err());
+
+        let (map, guards) = load_guards(SAMPLE);
+        let guard = guards[0]
+            .clone()
+            .with_rule(TurnRule::Sequence(vec![Turn::Right]));
+        let visited = guard.clone().find_visited(&map).unwrap();
+        assert_eq!(visited.len(), 41);
+        assert_eq!(find_stucks(&map, &guard, &visited), 6);
+
+        let guard = guards[0].clone().with_rule(TurnRule::Reverse);
+        assert_eq!(guard.clone().find_visited(&map).unwrap().len(), 9);
+
+        let guard = guards[0].clone().with_rule(TurnRule::Alternate);
+        let trace = guard.clone().trace(&map);
+        assert_eq!(trace.path[..7].last(), Some(&Pos { x: 4, y: 1 }));
+        assert_eq!(trace.at(7), Some(Pos { x: 3, y: 1 }));
+    }
+
+    #[test]
+    fn test_tiles() {
+        let (map, guards) = load_guards("e....\n.....\n^....\n");
+        assert_eq!(guards[0].clone().find_visited(&map).unwrap().len(), 7);
+
+        let (map, guards) = load_guards(".e<..\n");
+        let trace = guards[0].clone().trace(&map);
+        assert_eq!(trace.path, vec![Pos { x: 2, y: 0 }; 2]);
+
+        let (map, guards) = load_guards("..A..\n.....\n..^..\n.....\nA....\n");
+        assert_eq!(map.get(Pos { x: 2, y: 0 }), Some(Square::Teleport('A')));
+        let trace = guards[0].clone().trace(&map);
+        assert_eq!(trace.path.len(), 8);
+        assert_eq!(trace.at(3), Some(Pos { x: 0, y: 4 }));
+        assert_eq!(trace.visited().len(), 8);
+
+        let (map, guards) = load_guards(".3.\n.^.\n");
+        let trace = guards[0].clone().trace(&map);
+        assert_eq!(trace.path.len(), 5);
+        assert_eq!(trace.visited().len(), 2);
+
+        let (map, guards) = load_guards("A.<.A\n");
+        assert!(guards[0].clone().find_visited(&map).is_none());
+        assert_eq!(guards[0].clone().trace(&map).visited().len(), 5);
+    }
 }
@@ -0,0 +1,140 @@
+use std::str::FromStr;
+
+use super::{map::load_guards, stuck::find_stucks};
+
+/// Map dimensions written as `WIDTHxHEIGHT`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Size {
+    pub width: u16,
+    pub height: u16,
+}
+
+impl FromStr for Size {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s
+            .split_once('x')
+            .ok_or_else(|| format!("expected WIDTHxHEIGHT, got '{}'", s))?;
+        let width: u16 = width.parse().map_err(|e| format!("bad width: {}", e))?;
+        let height: u16 = height.parse().map_err(|e| format!("bad height: {}", e))?;
+        if width == 0 || height == 0 {
+            Err("map can't be empty".to_string())
+        } else {
+            Ok(Self { width, height })
+        }
+    }
+}
+
+/// SplitMix64, so the same seed always gives the same map.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0.0..1.0`.
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % (n as u64)) as usize
+    }
+}
+
+/// A map where each square is an obstacle with probability `density`, with a
+/// single guard facing north on one of the remaining squares.
+pub fn generate(size: Size, density: f64, seed: u64) -> String {
+    let mut rng = Rng(seed);
+    let (width, height) = (size.width as usize, size.height as usize);
+    let mut squares =
+        Vec::from_iter((0..width * height).map(|_| if rng.unit() < density { '#' } else { '.' }));
+    let empty = Vec::from_iter((0..squares.len()).filter(|&i| squares[i] == '.'));
+    let guard = if empty.is_empty() {
+        rng.below(squares.len())
+    } else {
+        empty[rng.below(empty.len())]
+    };
+    squares[guard] = '^';
+
+    let mut map = String::with_capacity((width + 1) * height);
+    for row in squares.chunks(width) {
+        map.extend(row);
+        map.push('\n');
+    }
+    map
+}
+
+/// How many places an obstacle would trap the guard, or `None` if the guard
+/// is trapped already.
+pub fn count_stucks(map: &str) -> Option<usize> {
+    let (map, guards) = load_guards(map);
+    let visited = guards[0].clone().find_visited(&map)?;
+    Some(find_stucks(&map, &guards[0], &visited))
+}
+
+/// Try seeds from `seed` onwards until a map with exactly `target` stuck
+/// positions turns up, giving back the seed that worked and its map.
+pub fn search(
+    size: Size,
+    density: f64,
+    seed: u64,
+    target: usize,
+    attempts: usize,
+) -> Option<(u64, String)> {
+    (0..attempts as u64)
+        .map(|i| seed.wrapping_add(i))
+        .map(|seed| (seed, generate(size, density, seed)))
+        .find(|(_, map)| count_stucks(map) == Some(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_stucks, generate, search, Size};
+
+    #[test]
+    fn test_generate() {
+        let size: Size = "12x7".parse().unwrap();
+        assert_eq!(
+            size,
+            Size {
+                width: 12,
+                height: 7
+            }
+        );
+        assert!("12".parse::<Size>().is_err());
+        assert!("0x3".parse::<Size>().is_err());
+
+        let map = generate(size, 0.2, 42);
+        assert_eq!(map, generate(size, 0.2, 42));
+        assert_ne!(map, generate(size, 0.2, 43));
+        assert_eq!(map.lines().count(), 7);
+        assert!(map.lines().all(|line| line.len() == 12));
+        assert_eq!(map.matches('^').count(), 1);
+
+        let map = generate(size, 0.0, 1);
+        assert_eq!(map.matches('#').count(), 0);
+        assert_eq!(count_stucks(&map), Some(0));
+    }
+
+    #[test]
+    fn test_search() {
+        let size = Size {
+            width: 16,
+            height: 16,
+        };
+        let target = (100..)
+            .find_map(|seed| count_stucks(&generate(size, 0.1, seed)).filter(|&n| n > 0))
+            .unwrap();
+        let (seed, map) = search(size, 0.1, 0, target, 1000).unwrap();
+        assert_eq!(map, generate(size, 0.1, seed));
+        assert_eq!(count_stucks(&map), Some(target));
+        assert_eq!(search(size, 0.1, 0, 10_000, 10), None);
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+};
+
+use super::{
+    dir::{Dir, ALL_DIRS},
+    guard::{Guard, State},
+    map::{Map, Pos, Square},
+};
+
+/// What happened on one square over a patrol.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SquareStats {
+    /// The step the guard first stood here.
+    pub first_visit: usize,
+    /// How many times the guard arrived here.
+    pub visits: usize,
+    /// Which ways the guard faced while standing here.
+    pub dirs: DirSet,
+}
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct DirSet(u8);
+
+impl DirSet {
+    fn bit(dir: Dir) -> u8 {
+        1 << ALL_DIRS.iter().position(|&d| d == dir).unwrap()
+    }
+
+    pub fn insert(&mut self, dir: Dir) {
+        self.0 |= Self::bit(dir);
+    }
+
+    pub fn contains(self, dir: Dir) -> bool {
+        self.0 & Self::bit(dir) != 0
+    }
+}
+
+impl std::fmt::Display for DirSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (dir, name) in ALL_DIRS.iter().zip("NSEW".chars()) {
+            if self.contains(*dir) {
+                write!(f, "{}", name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Per-square statistics for one or more patrols over the same map.
+pub struct Heatmap {
+    map: Map,
+    squares: HashMap<Pos, SquareStats>,
+}
+
+impl Heatmap {
+    pub fn new(map: &Map) -> Self {
+        Self {
+            map: map.clone(),
+            squares: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<SquareStats> {
+        self.squares.get(&pos).copied()
+    }
+
+    /// Walk a guard until it leaves the map or starts repeating itself,
+    /// adding what it did to the statistics. Returns false if it looped, in
+    /// which case the loop has been counted once.
+    pub fn survey(&mut self, mut guard: Guard) -> bool {
+        let mut seen: HashSet<State> = HashSet::new();
+        let mut last_pos: Option<Pos> = None;
+        let mut step = 0;
+        while let Some(state) = guard.state() {
+            if !seen.insert(state) {
+                return false;
+            }
+            let stats = self.squares.entry(state.pos).or_insert(SquareStats {
+                first_visit: step,
+                visits: 0,
+                dirs: DirSet::default(),
+            });
+            stats.first_visit = stats.first_visit.min(step);
+            if last_pos != Some(state.pos) {
+                stats.visits += 1;
+            }
+            stats.dirs.insert(state.dir);
+            last_pos = Some(state.pos);
+            guard.step(&self.map);
+            step += 1;
+        }
+        true
+    }
+
+    fn max<F>(&self, f: F) -> usize
+    where
+        F: Fn(&SquareStats) -> usize,
+    {
+        self.squares.values().map(f).max().unwrap_or(0).max(1)
+    }
+
+    fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.map.width;
+        let height = self.map.height() as u16;
+        (0..height).flat_map(move |y| (0..width).map(move |x| Pos { x, y }))
+    }
+
+    /// Greyscale image, brighter for squares visited more often.
+    pub fn write_pgm<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "P5\n{} {}\n255", self.map.width, self.map.height())?;
+        let max = self.max(|s| s.visits);
+        let pixels = Vec::from_iter(self.positions().map(|pos| {
+            self.get(pos)
+                .map(|s| (s.visits * 255 / max) as u8)
+                .unwrap_or(0)
+        }));
+        w.write_all(&pixels)
+    }
+
+    /// Color image going from blue for squares reached early to red for
+    /// squares reached late, with obstacles in grey.
+    pub fn write_ppm<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "P6\n{} {}\n255", self.map.width, self.map.height())?;
+        let max = self.max(|s| s.first_visit);
+        let mut pixels: Vec<u8> = Vec::with_capacity(self.map.width as usize * 3);
+        for pos in self.positions() {
+            let rgb = if let Some(stats) = self.get(pos) {
+                let t = (stats.first_visit * 255 / max) as u8;
+                [t, 0, 255 - t]
+            } else if self.map.get(pos) == Some(Square::Empty) {
+                [0, 0, 0]
+            } else {
+                [128, 128, 128]
+            };
+            pixels.extend(rgb);
+        }
+        w.write_all(&pixels)
+    }
+
+    /// One row per visited square, top to bottom.
+    pub fn write_csv<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "x,y,first_visit,visits,dirs")?;
+        for pos in self.positions() {
+            if let Some(s) = self.get(pos) {
+                writeln!(
+                    w,
+                    "{},{},{},{},{}",
+                    pos.x, pos.y, s.first_visit, s.visits, s.dirs
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day06::{load_guards, Dir, Pos};
+
+    use super::Heatmap;
+
+    #[test]
+    fn test_heatmap() {
+        let (map, guards) = load_guards(".#.\n...\n.^.\n");
+        let mut heatmap = Heatmap::new(&map);
+        assert!(heatmap.survey(guards[0].clone()));
+
+        let corner = heatmap.get(Pos { x: 1, y: 1 }).unwrap();
+        assert_eq!(corner.first_visit, 1);
+        assert_eq!(corner.visits, 1);
+        assert!(corner.dirs.contains(Dir::N) && corner.dirs.contains(Dir::E));
+        assert_eq!(corner.dirs.to_string(), "NE");
+        assert_eq!(heatmap.get(Pos { x: 2, y: 1 }).unwrap().first_visit, 3);
+        assert_eq!(heatmap.get(Pos { x: 0, y: 0 }), None);
+
+        let mut csv: Vec<u8> = Vec::new();
+        heatmap.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "x,y,first_visit,visits,dirs\n1,1,1,1,NE\n2,1,3,1,E\n1,2,0,1,N\n"
+        );
+
+        let mut pgm: Vec<u8> = Vec::new();
+        heatmap.write_pgm(&mut pgm).unwrap();
+        assert_eq!(pgm, b"P5\n3 3\n255\n\0\0\0\0\xff\xff\0\xff\0");
+
+        let mut ppm: Vec<u8> = Vec::new();
+        heatmap.write_ppm(&mut ppm).unwrap();
+        assert_eq!(ppm.len(), "P6\n3 3\n255\n".len() + 27);
+        assert_eq!(&ppm[ppm.len() - 6..ppm.len() - 3], &[0, 0, 255]);
+    }
+
+    #[test]
+    fn test_loop() {
+        let (map, guards) = load_guards("A.<.A\n");
+        let mut heatmap = Heatmap::new(&map);
+        assert!(!heatmap.survey(guards[0].clone()));
+        assert_eq!(heatmap.get(Pos { x: 0, y: 0 }).unwrap().visits, 1);
+        assert_eq!(heatmap.get(Pos { x: 2, y: 0 }).unwrap().visits, 1);
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use super::{
+    guard::{Guard, State},
+    map::{Map, Pos, Square},
+};
+
+/// A patrol that can be replayed under small changes to the map.
+///
+/// Every step remembers which square it looked at. Placing or removing an
+/// obstacle only replays the patrol from the first step that looked at that
+/// square; everything before it is kept.
+pub struct PatrolSession {
+    map: Map,
+    guard: Guard,
+    /// The guard's state before each step.
+    history: Vec<State>,
+    /// The square each step looked at.
+    probes: Vec<Option<Pos>>,
+    first_probe: HashMap<Pos, usize>,
+    first_visit: HashMap<Pos, usize>,
+    seen: HashMap<State, usize>,
+    looped: bool,
+}
+
+impl PatrolSession {
+    pub fn new(map: Map, guard: Guard) -> Self {
+        let start = guard.state().unwrap();
+        let mut session = Self {
+            map,
+            guard,
+            history: vec![start],
+            probes: Vec::new(),
+            first_probe: HashMap::new(),
+            first_visit: HashMap::from([(start.pos, 0)]),
+            seen: HashMap::from([(start, 0)]),
+            looped: false,
+        };
+        session.run();
+        session
+    }
+
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    pub fn visited_count(&self) -> usize {
+        self.first_visit.len()
+    }
+
+    pub fn is_loop(&self) -> bool {
+        self.looped
+    }
+
+    /// Put an obstacle on an empty square. Returns false if the square
+    /// isn't empty or is where the guard starts.
+    pub fn place(&mut self, pos: Pos) -> bool {
+        if self.map.get(pos) != Some(Square::Empty) || pos == self.history[0].pos {
+            return false;
+        }
+        self.map.put(pos, Square::Obstacle);
+        self.replay_from(pos);
+        true
+    }
+
+    /// Clear an obstacle. Returns false if there isn't one there.
+    pub fn remove(&mut self, pos: Pos) -> bool {
+        if self.map.get(pos) != Some(Square::Obstacle) {
+            return false;
+        }
+        self.map.put(pos, Square::Empty);
+        self.replay_from(pos);
+        true
+    }
+
+    /// Forget everything from the first step that looked at `pos`, then
+    /// carry on from there.
+    fn replay_from(&mut self, pos: Pos) {
+        let Some(&step) = self.first_probe.get(&pos) else {
+            return;
+        };
+        self.history.truncate(step + 1);
+        self.probes.truncate(step);
+        self.first_probe.retain(|_, i| *i < step);
+        self.first_visit.retain(|_, i| *i <= step);
+        self.seen.retain(|_, i| *i <= step);
+        self.guard.restore(self.history[step]);
+        self.run();
+    }
+
+    /// Walk the guard until it leaves the map or repeats itself.
+    fn run(&mut self) {
+        loop {
+            let step = self.history.len() - 1;
+            let probe = self.guard.probe();
+            self.probes.push(probe);
+            if let Some(probe) = probe {
+                self.first_probe.entry(probe).or_insert(step);
+            }
+            self.guard.step(&self.map);
+            let Some(state) = self.guard.state() else {
+                self.looped = false;
+                return;
+            };
+            if self.seen.contains_key(&state) {
+                self.looped = true;
+                return;
+            }
+            self.seen.insert(state, step + 1);
+            self.first_visit.entry(state.pos).or_insert(step + 1);
+            self.history.push(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day06::{load_guards, Pos, Square};
+
+    use super::PatrolSession;
+
+    #[test]
+    fn test_session() {
+        let input = "....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#...
+";
+        let (map, guards) = load_guards(input);
+        let mut session = PatrolSession::new(map.clone(), guards[0].clone());
+        assert_eq!(session.visited_count(), 41);
+        assert!(!session.is_loop());
+
+        let mut loops = 0;
+        for y in 0..10 {
+            for x in 0..10 {
+                let pos = Pos { x, y };
+                if session.place(pos) {
+                    let mut new_map = map.clone();
+                    new_map.put(pos, Square::Obstacle);
+                    let visited = guards[0].clone().find_visited(&new_map);
+                    assert_eq!(session.is_loop(), visited.is_none());
+                    if let Some(visited) = visited {
+                        assert_eq!(session.visited_count(), visited.len());
+                    } else {
+                        loops += 1;
+                    }
+                    assert!(session.remove(pos));
+                    assert_eq!(session.visited_count(), 41);
+                }
+            }
+        }
+        assert_eq!(loops, 6);
+
+        assert!(!session.place(Pos { x: 4, y: 6 }));
+        assert!(!session.remove(Pos { x: 3, y: 3 }));
+        assert!(session.remove(Pos { x: 4, y: 0 }));
+        assert!(!session.is_loop());
+        assert_eq!(session.map().get(Pos { x: 4, y: 0 }), Some(Square::Empty));
+    }
+}
@@ -1,310 +1,420 @@
 use std::fs;
 
+use audit::audit;
+use num::Number;
+#[cfg(test)]
 use perms::OpPerms;
 
-pub fn day07() {
-    let input = fs::read_to_string("day07_input.txt").unwrap();
-    let sum = part1::part1(&input);
-    println!("total calibration result: {}", sum);
-    let sum = part2::part2(&input);
-    println!("total concatted calibration result: {}", sum);
+pub use audit::Witnesses;
+pub use num::Numbers;
+pub use ops::{Op, OpSet};
+pub use precedence::ConcatBinds;
+use precedence::{Order, Precedence};
+
+#[derive(clap::Args)]
+#[group(id = "day07")]
+pub struct Options {
+    /// Show which operators satisfy each calibration line
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub witnesses: Option<Witnesses>,
+
+    /// Solve with these operators instead of parts 1 and 2, e.g. `+,*,||,-,/,^,min,max`
+    /// (`||16` glues digits together in base 16)
+    #[arg(long, value_name = "OPS")]
+    pub ops: Option<OpSet>,
+
+    /// Do `^` (right to left), then `*` and `/`, then `+` and `-`, instead of
+    /// going strictly left to right
+    #[arg(long)]
+    pub precedence: bool,
+
+    /// Where `||` comes in the order of operations, with `--precedence`
+    #[arg(long, value_enum, value_name = "LEVEL", default_value_t)]
+    pub concat_binds: ConcatBinds,
+
+    /// How many threads to check calibration lines on [default: one per core]
+    #[arg(long, value_name = "N")]
+    pub threads: Option<usize>,
+
+    /// What kind of numbers to do the arithmetic with
+    #[arg(long, value_enum, value_name = "TYPE", default_value_t)]
+    pub numbers: Numbers,
 }
 
-type Num = u64;
-type Out1 = Num;
-type Out2 = Num;
-type Test = (Num, Vec<Num>);
-
-mod part1 {
-    use crate::day07::parse::parse;
-
-    use super::{good, Binary, Num, Operator, Out1};
+impl Options {
+    fn order(&self) -> Order {
+        if self.precedence {
+            Order::Precedence(Precedence {
+                concat: self.concat_binds,
+            })
+        } else {
+            Order::LeftToRight
+        }
+    }
 
-    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-    pub enum Op {
-        Add,
-        Mul,
+    fn threads(&self) -> usize {
+        self.threads.unwrap_or_else(parallel::available)
     }
+}
 
-    impl Operator for Op {
-        const FIRST: Self = Self::Add;
+pub fn day07(options: &Options) {
+    let input = fs::read_to_string("day07_input.txt").unwrap();
+    match options.numbers {
+        Numbers::Default => run::<Num>(options, &input),
+        Numbers::I64 => run::<i64>(options, &input),
+        Numbers::U128 => run::<u128>(options, &input),
+        Numbers::Prime => run::<num::Prime>(options, &input),
+    }
+}
 
-        fn next(&self) -> Option<Self>
-        where
-            Self: Sized,
-        {
-            match self {
-                Op::Add => Some(Self::Mul),
-                Op::Mul => None,
+fn run<N: Number>(options: &Options, input: &str) {
+    let order = options.order();
+    let threads = options.threads();
+    let sets = match &options.ops {
+        Some(ops) => vec![ops.clone()],
+        None => vec![OpSet(part1::OPS.to_vec()), OpSet(part2::OPS.to_vec())],
+    };
+    let (tests, gaps) = parse::parse_gaps::<N>(input);
+    if !gaps.is_empty() {
+        fill_gaps(&sets, order, &gaps);
+    }
+    if options.ops.is_some() || order != Order::LeftToRight {
+        for ops in &sets {
+            if let Some(mode) = options.witnesses {
+                println!("{}:", ops);
+                for line in audit(&ops.0, order, &tests, mode) {
+                    println!("{}", line);
+                }
             }
+            let sum = total(&tests, threads, |t| order.good(&ops.0, t));
+            println!("total calibration result with {} ({}): {}", ops, order, sum);
+        }
+        return;
+    }
+    if let Some(mode) = options.witnesses {
+        println!("part 1:");
+        for line in audit(&part1::OPS, order, &tests, mode) {
+            println!("{}", line);
+        }
+        println!("part 2:");
+        for line in audit(&part2::OPS, order, &tests, mode) {
+            println!("{}", line);
         }
     }
+    let sum: N = part1::part1(input, threads);
+    println!("total calibration result: {}", sum);
+    let sum: N = part2::part2(input, threads);
+    println!("total concatted calibration result: {}", sum);
+}
 
-    impl Binary<Num> for Op {
-        fn exec(&self, left: Num, right: Num) -> Num {
-            match self {
-                Op::Add => left + right,
-                Op::Mul => left * right,
-            }
+/// Print what could go in place of each `?`.
+fn fill_gaps<N: Number>(sets: &[OpSet], order: Order, gaps: &[Gap<N>]) {
+    if order != Order::LeftToRight {
+        println!("can't fill in '?' when going {}", order);
+        return;
+    }
+    for ops in sets {
+        println!("filling in '?' with {}:", ops);
+        for gap in gaps {
+            println!("{}", missing::describe(gap, &missing::solve(&ops.0, gap)));
         }
     }
+}
+
+#[cfg(not(feature = "bigint"))]
+type Num = u64;
+#[cfg(feature = "bigint")]
+type Num = num_bigint::BigUint;
+type Test<N = Num> = (N, Vec<N>);
+/// A line with a `?`: the label, the numbers around the `?`, and where it
+/// goes among them.
+type Gap<N = Num> = (N, Vec<N>, usize);
+
+mod part1 {
+    use super::{good, num::Number, parse::parse, total, Op};
+
+    pub const OPS: [Op; 2] = [Op::Add, Op::Mul];
 
-    pub fn part1(input: &str) -> Out1 {
+    pub fn part1<N: Number>(input: &str, threads: usize) -> N {
         let tests = parse(input);
-        let sum: Out1 = tests
-            .iter()
-            .filter(|t| good::<Op, Num>(t))
-            .map(|t| t.0)
-            .sum();
-        sum
+        total(&tests, threads, |t| good(&OPS, t))
     }
 }
 
-fn good<T, N>(test: &(N, Vec<N>)) -> bool
+/// Add up the labels of the lines that pass, which had better fit. They're
+/// added in input order however many threads did the checking.
+fn total<N, F>(tests: &[Test<N>], threads: usize, good: F) -> N
 where
-    T: Operator + Binary<N> + Clone + Sized,
-    N: Sized + PartialEq + Clone,
+    N: Number,
+    F: Fn(&Test<N>) -> bool + Sync,
+{
+    let passed = parallel::check(tests, threads, good);
+    tests
+        .iter()
+        .zip(passed)
+        .filter(|(_, passed)| *passed)
+        .fold(N::from_digit(0), |sum, (t, _)| {
+            sum.try_add(&t.0).expect("calibration total overflowed")
+        })
+}
+
+/// Whether some choice of operators makes the numbers come out to the label.
+fn good<T, N>(ops: &[T], test: &(N, Vec<N>)) -> bool
+where
+    T: Inverse<N> + Clone,
+    N: PartialEq + Clone,
+{
+    let (label, nums) = test;
+    solve::witness(ops, label.clone(), nums).is_some()
+}
+
+/// The same as `good`, but trying every combination of operators.
+#[cfg(test)]
+fn good_brute<T, N>(ops: &[T], test: &(N, Vec<N>)) -> bool
+where
+    T: Binary<N> + Clone,
+    N: PartialEq + Clone,
 {
     let (label, nums) = test;
-    for ops in OpPerms::<T>::new(nums.len() - 1) {
-        if exec(nums.as_slice(), &ops) == label.clone() {
+    for ops in OpPerms::new(ops, nums.len() - 1) {
+        if exec(nums.as_slice(), &ops).as_ref() == Some(label) {
             return true;
         }
     }
     false
 }
 
-mod part2 {
-    use super::{good, parse::parse, Binary, Num, Operator, Out2};
-
-    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-    pub enum Op {
-        Add,
-        Mul,
-        Concat,
-    }
-
-    impl Operator for Op {
-        const FIRST: Self = Self::Add;
-
-        fn next(&self) -> Option<Self>
-        where
-            Self: Sized,
-        {
-            match self {
-                Op::Add => Some(Self::Mul),
-                Op::Mul => Some(Self::Concat),
-                Op::Concat => None,
-            }
-        }
-    }
+mod solve;
 
-    impl Binary<Num> for Op {
-        fn exec(&self, left: Num, right: Num) -> Num {
-            match self {
-                Op::Add => left + right,
-                Op::Mul => left * right,
-                Op::Concat => concat_num(left, right),
-            }
-        }
-    }
+mod audit;
 
-    pub fn concat_num(left: Num, right: Num) -> Num {
-        left * (10 as Num).pow(ndigits(right) as u32) + right
-    }
+mod num;
 
-    fn ndigits(n: Num) -> u8 {
-        if n == 0 {
-            1
-        } else {
-            let mut n = n;
-            let mut i = 0;
-            while n != 0 {
-                i += 1;
-                n /= 10;
-            }
-            i
-        }
-    }
+mod ops;
+
+mod precedence;
+
+mod parallel;
 
-    pub fn part2(input: &str) -> Out2 {
+mod missing;
+
+mod part2 {
+    use super::{good, num::Number, parse::parse, total, Op};
+
+    pub const OPS: [Op; 3] = [Op::Add, Op::Mul, Op::Concat(10)];
+
+    pub fn part2<N: Number>(input: &str, threads: usize) -> N {
         let tests = parse(input);
-        let sum: Out2 = tests
-            .iter()
-            .filter(|t| good::<Op, Num>(t))
-            .map(|t| t.0)
-            .sum();
-        sum
+        total(&tests, threads, |t| good(&OPS, t))
     }
 }
 
-trait Operator {
-    const FIRST: Self;
-    fn next(&self) -> Option<Self>
-    where
-        Self: Sized;
-
-    fn add_zero_with_carry(&self, carry: bool) -> (Self, bool)
-    where
-        Self: Clone,
-    {
-        if carry {
-            if let Some(new_op) = self.next() {
-                (new_op, false)
+/// Combining two numbers, or `None` if the result doesn't fit.
+trait Binary<N> {
+    fn exec(&self, left: N, right: N) -> Option<N>;
+}
+
+/// Every `left` for which `left op right == result`.
+#[derive(PartialEq, Debug)]
+enum Preimage<N> {
+    None,
+    One(N),
+    /// Any `left` at all will do.
+    Any,
+    /// Too many `left`s to list, so the rest has to be run forwards and
+    /// checked.
+    Unknown,
+}
+
+impl<N: Number> Preimage<N> {
+    fn sub(result: &N, right: &N) -> Self {
+        result.try_sub(right).into()
+    }
+
+    fn div(result: &N, right: &N) -> Self {
+        if right.is_zero() {
+            if result.is_zero() {
+                Self::Any
             } else {
-                (Self::FIRST.clone(), true)
+                Self::None
             }
         } else {
-            (self.clone(), false)
+            result.exact_div(right).into()
         }
     }
 }
 
-fn inc_ops<T>(ops: &[T]) -> (Vec<T>, bool)
-where
-    T: Operator + Sized + Clone,
-{
-    let mut new_ops = Vec::with_capacity(ops.len());
-    let mut carry = true;
-    for op in ops.iter().rev() {
-        let (new_op, new_carry) = op.add_zero_with_carry(carry);
-        new_ops.push(new_op);
-        carry = new_carry;
-    }
-    new_ops.reverse();
-    (new_ops, carry)
+impl<N> From<Option<N>> for Preimage<N> {
+    fn from(value: Option<N>) -> Self {
+        value.map_or(Self::None, Self::One)
+    }
 }
 
-trait Binary<N> {
-    fn exec(&self, left: N, right: N) -> N;
+/// An operator that can be run backwards.
+trait Inverse<N>: Binary<N> {
+    fn unexec(&self, result: N, right: N) -> Preimage<N>;
+}
+
+/// An operator that can be run backwards from the other side, for when it's
+/// the number on the right that isn't known. `Any` and `Unknown` mean the
+/// same as for `Inverse`, but with `right` in place of `left`.
+trait RightInverse<N>: Binary<N> {
+    fn unexec_right(&self, result: N, left: N) -> Preimage<N>;
 }
 
-fn exec<T, N>(args: &[N], ops: &[T]) -> N
+/// Run the operators left to right, or `None` if anything overflows.
+fn exec<T, N>(args: &[N], ops: &[T]) -> Option<N>
 where
-    T: Sized + Operator + Binary<N>,
+    T: Binary<N>,
     N: Clone,
 {
     let mut args = args.iter().cloned();
     let mut result = args.next().unwrap();
     for op in ops {
-        result = op.exec(result, args.next().unwrap());
+        result = op.exec(result, args.next().unwrap())?;
     }
     assert!(args.next().is_none());
-    result
+    Some(result)
 }
 
 mod perms {
-    use std::mem;
-
-    use super::{inc_ops, Operator};
-
-    /// All permutations of a given number of operators.
-    /// Since there's only 2 operators, we simulate binary incrementing.
-    pub struct OpPerms<T> {
-        ops: Vec<T>,
+    /// All permutations of a given number of operators, counted through
+    /// like an odometer whose digits are the operators in `ops`.
+    pub struct OpPerms<'a, T> {
+        ops: &'a [T],
+        digits: Vec<usize>,
         end: bool,
     }
 
-    impl<T> OpPerms<T>
-    where
-        T: Sized + Operator + Clone,
-    {
-        pub fn new(num_ops: usize) -> Self {
+    impl<'a, T> OpPerms<'a, T> {
+        pub fn new(ops: &'a [T], num_ops: usize) -> Self {
             assert!(num_ops > 0);
             Self {
-                ops: vec![T::FIRST.clone(); num_ops],
-                end: false,
+                ops,
+                digits: vec![0; num_ops],
+                end: ops.is_empty(),
             }
         }
     }
 
-    impl<T> Iterator for OpPerms<T>
-    where
-        T: Sized + Operator + Clone,
-    {
+    impl<T: Clone> Iterator for OpPerms<'_, T> {
         type Item = Vec<T>;
 
         fn next(&mut self) -> Option<Self::Item> {
             if self.end {
-                None
-            } else {
-                let (new_ops, overflow) = inc_ops(&self.ops);
-                if overflow {
-                    self.end = true;
+                return None;
+            }
+            let item = Vec::from_iter(self.digits.iter().map(|&i| self.ops[i].clone()));
+            self.end = true;
+            for digit in self.digits.iter_mut().rev() {
+                *digit += 1;
+                if *digit < self.ops.len() {
+                    self.end = false;
+                    break;
                 }
-                Some(mem::replace(&mut self.ops, new_ops))
+                *digit = 0;
             }
+            Some(item)
         }
     }
 }
 mod parse {
     //! tests: line+ trailer
-    //! line: label nums '\n'
+    //! line: label operands '\n'
     //! label: num ':'
-    //! nums: num num+
+    //! operands: operand operand+
+    //! operand: num | '?'
+    //! num: '-'? digit+
     //! trailer: '\n'*
+    //!
+    //! At most one operand per line can be a `?`.
     use std::iter::Peekable;
 
     use token::{Token, Tokenizer};
 
-    use super::{Num, Test};
+    use super::{num::Number, Gap, Test};
+
+    /// Just the lines without a `?`.
+    pub fn parse<N: Number>(input: &str) -> Vec<Test<N>> {
+        parse_gaps(input).0
+    }
 
-    pub fn parse(input: &str) -> Vec<Test> {
-        let mut t: Peekable<Tokenizer<'_>> = Tokenizer::new(input).peekable();
-        let mut tests = vec![line(&mut t)];
-        while let Some(&Token::Num(_)) = t.peek() {
-            tests.push(line(&mut t));
+    /// The lines without a `?`, and the lines with one.
+    pub fn parse_gaps<N: Number>(input: &str) -> (Vec<Test<N>>, Vec<Gap<N>>) {
+        let mut t: Peekable<Tokenizer<'_, N>> = Tokenizer::new(input).peekable();
+        let mut tests: Vec<Test<N>> = Vec::new();
+        let mut gaps: Vec<Gap<N>> = Vec::new();
+        loop {
+            let (label, operands) = line(&mut t);
+            let missing = Vec::from_iter((0..operands.len()).filter(|&i| operands[i].is_none()));
+            let nums = Vec::from_iter(operands.into_iter().flatten());
+            match missing[..] {
+                [] => tests.push((label, nums)),
+                [i] => gaps.push((label, nums, i)),
+                _ => panic!("more than one '?' in a line"),
+            }
+            if !matches!(t.peek(), Some(&Token::Num(_))) {
+                break;
+            }
         }
         trailer(&mut t);
         assert!(t.peek().is_none());
-        tests
+        (tests, gaps)
     }
 
-    fn trailer(t: &mut Peekable<Tokenizer<'_>>) {
+    fn trailer<N: Number>(t: &mut Peekable<Tokenizer<'_, N>>) {
         while let Some(&Token::NewLine) = t.peek() {
             t.next().unwrap();
         }
     }
 
-    fn line(t: &mut Peekable<Tokenizer<'_>>) -> (Num, Vec<Num>) {
+    fn line<N: Number>(t: &mut Peekable<Tokenizer<'_, N>>) -> (N, Vec<Option<N>>) {
         let label = label(t);
-        let nums = nums(t);
+        let operands = operands(t);
         assert_eq!(t.next(), Some(Token::NewLine));
-        (label, nums)
+        (label, operands)
     }
 
-    fn label(t: &mut Peekable<Tokenizer<'_>>) -> Num {
+    fn label<N: Number>(t: &mut Peekable<Tokenizer<'_, N>>) -> N {
         let num = t.next().unwrap().num();
         assert_eq!(t.next(), Some(Token::Colon));
         num
     }
 
-    fn nums(t: &mut Peekable<Tokenizer<'_>>) -> Vec<Num> {
-        let mut nums: Vec<Num> = Vec::with_capacity(2);
-        nums.push(t.next().unwrap().num());
-        nums.push(t.next().unwrap().num());
+    fn operands<N: Number>(t: &mut Peekable<Tokenizer<'_, N>>) -> Vec<Option<N>> {
+        let mut operands: Vec<Option<N>> = Vec::with_capacity(2);
+        operands.push(operand(t));
+        operands.push(operand(t));
 
-        while let Some(Token::Num(num)) = t.peek() {
-            nums.push(*num);
-            t.next().unwrap();
+        while let Some(Token::Num(_) | Token::Missing) = t.peek() {
+            operands.push(operand(t));
+        }
+        operands
+    }
+
+    /// A number, or `None` for a `?`.
+    fn operand<N: Number>(t: &mut Peekable<Tokenizer<'_, N>>) -> Option<N> {
+        match t.next().unwrap() {
+            Token::Missing => None,
+            token => Some(token.num()),
         }
-        nums
     }
 
     mod token {
-        use std::{iter::Peekable, str::Chars};
+        use std::{iter::Peekable, marker::PhantomData, str::Chars};
 
-        use crate::day07::Num;
+        use crate::day07::num::Number;
 
-        #[derive(Clone, Copy, PartialEq, Debug)]
-        pub enum Token {
-            Num(Num),
+        #[derive(Clone, PartialEq, Debug)]
+        pub enum Token<N> {
+            Num(N),
             Colon,
             NewLine,
+            Missing,
         }
 
-        impl Token {
-            pub fn num(self) -> Num {
+        impl<N> Token<N> {
+            pub fn num(self) -> N {
                 if let Self::Num(i) = self {
                     i
                 } else {
@@ -313,14 +423,16 @@ mod parse {
             }
         }
 
-        pub struct Tokenizer<'a> {
+        pub struct Tokenizer<'a, N> {
             chars: Peekable<Chars<'a>>,
+            num: PhantomData<N>,
         }
 
-        impl<'a> Tokenizer<'a> {
+        impl<'a, N: Number> Tokenizer<'a, N> {
             pub fn new(s: &'a str) -> Self {
                 Self {
                     chars: s.chars().peekable(),
+                    num: PhantomData,
                 }
             }
 
@@ -328,12 +440,16 @@ mod parse {
                 self.chars.next().unwrap();
             }
 
-            fn num(&mut self) -> Num {
-                let mut i = self.chars.next().unwrap().to_digit(10).unwrap() as Num;
+            fn num(&mut self) -> N {
+                let ten = N::from_digit(10);
+                let mut i = N::from_digit(self.chars.next().unwrap().to_digit(10).unwrap());
                 while let Some(c) = self.chars.peek() {
                     if let Some(digit) = c.to_digit(10) {
                         self.advance();
-                        i = i * 10 + (digit as Num);
+                        i = i
+                            .try_mul(&ten)
+                            .and_then(|i| i.try_add(&N::from_digit(digit)))
+                            .expect("number too big");
                     } else {
                         break;
                     }
@@ -342,8 +458,8 @@ mod parse {
             }
         }
 
-        impl Iterator for Tokenizer<'_> {
-            type Item = Token;
+        impl<N: Number> Iterator for Tokenizer<'_, N> {
+            type Item = Token<N>;
 
             fn next(&mut self) -> Option<Self::Item> {
                 while let Some(c) = self.chars.peek() {
@@ -353,6 +469,19 @@ mod parse {
                     } else if *c == ':' {
                         self.advance();
                         return Some(Token::Colon);
+                    } else if *c == '?' {
+                        self.advance();
+                        return Some(Token::Missing);
+                    } else if *c == '-' {
+                        self.advance();
+                        assert!(
+                            self.chars.peek().is_some_and(|c| c.is_ascii_digit()),
+                            "expected a number after '-'"
+                        );
+                        let num = self.num().try_neg();
+                        return Some(Token::Num(
+                            num.expect("negative numbers need a signed number type"),
+                        ));
                     } else if c.is_ascii_digit() {
                         return Some(Token::Num(self.num()));
                     } else if c.is_whitespace() {
@@ -371,41 +500,47 @@ mod parse {
 mod tests {
 
     use crate::day07::{
-        good,
-        parse::parse,
+        good, good_brute,
+        num::{concat, split, Prime},
+        parse::{parse, parse_gaps},
         part1::{self},
-        part2::{self, concat_num},
+        part2::{self},
         perms::OpPerms,
-        Num, Test,
+        Num, Op, Preimage, Test,
     };
 
+    fn test(label: u32, nums: &[u32]) -> Test {
+        (
+            Num::from(label),
+            Vec::from_iter(nums.iter().map(|&n| Num::from(n))),
+        )
+    }
+
     #[test]
     fn test_op1() {
-        use part1::Op;
         let all = vec![
             vec![Op::Add, Op::Add],
             vec![Op::Add, Op::Mul],
             vec![Op::Mul, Op::Add],
             vec![Op::Mul, Op::Mul],
         ];
-        assert_eq!(all, Vec::from_iter(OpPerms::<Op>::new(2)))
+        assert_eq!(all, Vec::from_iter(OpPerms::new(&part1::OPS, 2)))
     }
 
     #[test]
     fn test_op2() {
-        use part2::Op;
         let all = vec![
             vec![Op::Add, Op::Add],
             vec![Op::Add, Op::Mul],
-            vec![Op::Add, Op::Concat],
+            vec![Op::Add, Op::Concat(10)],
             vec![Op::Mul, Op::Add],
             vec![Op::Mul, Op::Mul],
-            vec![Op::Mul, Op::Concat],
-            vec![Op::Concat, Op::Add],
-            vec![Op::Concat, Op::Mul],
-            vec![Op::Concat, Op::Concat],
+            vec![Op::Mul, Op::Concat(10)],
+            vec![Op::Concat(10), Op::Add],
+            vec![Op::Concat(10), Op::Mul],
+            vec![Op::Concat(10), Op::Concat(10)],
         ];
-        assert_eq!(all, Vec::from_iter(OpPerms::<Op>::new(2)));
+        assert_eq!(all, Vec::from_iter(OpPerms::new(&part2::OPS, 2)));
     }
 
     #[test]
@@ -415,42 +550,59 @@ mod tests {
 ";
         assert_eq!(
             parse(input),
-            vec![(123, vec![4, 56, 78]), (11, vec!(12, 1, 4, 12))]
+            vec![test(123, &[4, 56, 78]), test(11, &[12, 1, 4, 12])]
         );
     }
 
+    #[test]
+    fn test_parse_gaps() {
+        let input = "123: 4 ? 78
+11: 12 1 4 12
+7: ? 7
+";
+        let (tests, gaps) = parse_gaps(input);
+        assert_eq!(tests, vec![test(11, &[12, 1, 4, 12])]);
+        let (label, nums) = test(123, &[4, 78]);
+        assert_eq!(gaps[0], (label, nums, 1));
+        let (label, nums) = test(7, &[7]);
+        assert_eq!(gaps[1], (label, nums, 0));
+        assert_eq!(parse::<Num>(input).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "more than one '?'")]
+    fn test_parse_two_gaps() {
+        parse::<Num>("3: ? 1 ?\n");
+    }
+
     #[test]
     fn test_good() {
         use part1;
         use part2;
-        let test1: [Test; 3] = [
+        let test1: [(u64, Vec<u64>); 3] = [
             (190, vec![10, 19]),
             (3267, vec![81, 40, 27]),
             (292, vec![11, 6, 16, 20]),
         ];
         for test in &test1 {
-            assert!(good::<part1::Op, Num>(test));
+            assert!(good(&part1::OPS, test));
         }
-        let test2: [Test; 3] = [
+        let test2: [(u64, Vec<u64>); 3] = [
             (156, vec![15, 6]),
             (7290, vec![6, 8, 6, 15]),
             (192, vec![17, 8, 14]),
         ];
         for test in test1.iter().chain(test2.iter()) {
-            assert!(good::<part2::Op, Num>(test));
+            assert!(good(&part2::OPS, test));
         }
     }
 
     #[test]
     fn test_concat_good() {
-        use part2::Op;
-
-        assert!(good::<Op, Num>(&(156, vec![15, 6])));
+        assert!(good(&[Op::Concat(10)], &(156u64, vec![15, 6])));
     }
 
-    #[test]
-    fn test_examples() {
-        let input = "190: 10 19
+    pub const EXAMPLE: &str = "190: 10 19
 3267: 81 40 27
 83: 17 5
 156: 15 6
@@ -460,14 +612,86 @@ mod tests {
 21037: 9 7 18 13
 292: 11 6 16 20
 ";
-        assert_eq!(part1::part1(input), 3749);
-        assert_eq!(part2::part2(input), 11387);
+
+    #[test]
+    fn test_examples() {
+        for threads in [1, 4] {
+            assert_eq!(part1::part1::<Num>(EXAMPLE, threads), Num::from(3749u32));
+            assert_eq!(part2::part2::<Num>(EXAMPLE, threads), Num::from(11387u32));
+        }
+    }
+
+    #[test]
+    fn test_number_types() {
+        assert_eq!(part2::part2::<i64>(EXAMPLE, 1), 11387);
+        assert_eq!(part2::part2::<u128>(EXAMPLE, 1), 11387);
+        // Nothing in the example gets near p, so going mod p finds the same
+        // lines.
+        let sum: Prime = part2::part2(EXAMPLE, 1);
+        assert_eq!(sum, Prime::new(11387));
+    }
+
+    #[test]
+    fn test_negative() {
+        let tests: Vec<Test<i64>> = parse("-6: 3 -2\n-1: -3 2\n");
+        assert_eq!(tests, vec![(-6, vec![3, -2]), (-1, vec![-3, 2])]);
+        assert!(good(&part1::OPS, &tests[0]));
+        assert!(good(&part1::OPS, &tests[1]));
+        // There are no digits to glue on to a negative number.
+        assert!(!good(&[Op::Concat(10)], &(-32i64, vec![-3, 2])));
+    }
+
+    #[test]
+    #[should_panic(expected = "signed number type")]
+    fn test_negative_unsigned() {
+        parse::<u64>("-6: 3 -2\n");
+    }
+
+    #[test]
+    fn test_concat_base() {
+        // 0b101 || 0b11 = 0b10111
+        assert!(good(&[Op::Concat(2)], &(23u64, vec![5, 3])));
+        assert!(!good(&[Op::Concat(2)], &(53u64, vec![5, 3])));
+        let ops = [Op::Add, Op::Concat(16)];
+        assert!(good(&ops, &(0x1f2u64, vec![0x1f, 2])));
+        assert!(good(&ops, &(0x1f2u64, vec![0x1e, 1, 2])));
     }
 
     #[test]
     fn test_concat() {
-        assert_eq!(concat_num(123, 456), 123456);
-        assert_eq!(concat_num(1, 0), 10);
-        assert_eq!(concat_num(0, 123), 123);
+        assert_eq!(concat(&123u64, &456, 10), Some(123456));
+        assert_eq!(concat(&1u64, &0, 10), Some(10));
+        assert_eq!(concat(&0u64, &123, 10), Some(123));
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split(&123456u64, &456, 10), Some(123));
+        assert_eq!(split(&10u64, &0, 10), Some(1));
+        assert_eq!(split(&123u64, &123, 10), Some(0));
+        assert_eq!(split(&123456u64, &45, 10), None);
+        assert_eq!(Preimage::div(&0u64, &0), Preimage::Any);
+        assert_eq!(Preimage::div(&7u64, &2), Preimage::None);
+        assert_eq!(Preimage::sub(&2u64, &7), Preimage::None);
+    }
+
+    #[test]
+    fn test_brute() {
+        let tests: Vec<Test> = parse(EXAMPLE);
+        for test in &tests {
+            assert_eq!(good(&part1::OPS, test), good_brute(&part1::OPS, test));
+            assert_eq!(good(&part2::OPS, test), good_brute(&part2::OPS, test));
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        let edge = (u64::MAX, vec![u64::MAX / 2, 2, 1]);
+        assert!(good(&part1::OPS, &edge));
+        assert!(good_brute(&part1::OPS, &edge));
+        assert!(good_brute(&part2::OPS, &edge));
+        let over = (1, vec![u64::MAX, u64::MAX, 1]);
+        assert!(!good(&part2::OPS, &over));
+        assert!(!good_brute(&part2::OPS, &over));
     }
 }
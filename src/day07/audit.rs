@@ -0,0 +1,127 @@
+use std::fmt::Display;
+
+use clap::ValueEnum;
+
+use super::{num::Number, precedence::Order, Op};
+
+/// How much to show about the ways each calibration line can be satisfied.
+#[derive(Clone, Copy, PartialEq, Debug, ValueEnum)]
+pub enum Witnesses {
+    /// The first choice of operators found
+    First,
+    /// Every choice of operators
+    All,
+    /// How many choices of operators there are
+    Count,
+}
+
+/// Write an equation out in full, e.g. `3267 = 81 + 40 * 27`.
+pub fn equation<T, N>(label: &N, nums: &[N], ops: &[T]) -> String
+where
+    T: Display,
+    N: Display,
+{
+    let mut s = format!("{} = {}", label, nums[0]);
+    for (op, num) in ops.iter().zip(&nums[1..]) {
+        s += &format!(" {} {}", op, num);
+    }
+    s
+}
+
+/// One line per calibration line, or per way of satisfying it.
+pub fn audit<N: Number>(
+    ops: &[Op],
+    order: Order,
+    tests: &[(N, Vec<N>)],
+    mode: Witnesses,
+) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for (label, nums) in tests {
+        let unsolved = || {
+            let nums = Vec::from_iter(nums.iter().map(|n| n.to_string()));
+            format!("{}: {} has no solution", label, nums.join(" "))
+        };
+        match mode {
+            Witnesses::First => lines.push(
+                order
+                    .witness(ops, label.clone(), nums)
+                    .map(|ops| equation(label, nums, &ops))
+                    .unwrap_or_else(unsolved),
+            ),
+            Witnesses::All => {
+                let all = order.witnesses(ops, label.clone(), nums);
+                if all.is_empty() {
+                    lines.push(unsolved());
+                }
+                lines.extend(all.iter().map(|ops| equation(label, nums, ops)));
+            }
+            Witnesses::Count => {
+                let count = order.count(ops, label.clone(), nums);
+                let nums = Vec::from_iter(nums.iter().map(|n| n.to_string()));
+                lines.push(format!(
+                    "{}: {} has {} solutions",
+                    label,
+                    nums.join(" "),
+                    count
+                ));
+            }
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day07::{
+        part1,
+        precedence::{Order, Precedence},
+        Op,
+    };
+
+    use super::{audit, equation, Witnesses};
+
+    #[test]
+    fn test_equation() {
+        assert_eq!(
+            equation(&3267, &[81, 40, 27], &[Op::Add, Op::Mul]),
+            "3267 = 81 + 40 * 27"
+        );
+        assert_eq!(equation(&156, &[15, 6], &[Op::Concat(10)]), "156 = 15 || 6");
+        assert_eq!(equation(&2, &[3, 2], &[Op::Min]), "2 = 3 min 2");
+    }
+
+    #[test]
+    fn test_audit() {
+        let tests: Vec<(u64, Vec<u64>)> = vec![(3267, vec![81, 40, 27]), (83, vec![17, 5])];
+        assert_eq!(
+            audit(&part1::OPS, Order::LeftToRight, &tests, Witnesses::First),
+            vec!["3267 = 81 * 40 + 27", "83: 17 5 has no solution"]
+        );
+        assert_eq!(
+            audit(&part1::OPS, Order::LeftToRight, &tests, Witnesses::All),
+            vec![
+                "3267 = 81 * 40 + 27",
+                "3267 = 81 + 40 * 27",
+                "83: 17 5 has no solution"
+            ]
+        );
+        assert_eq!(
+            audit(&part1::OPS, Order::LeftToRight, &tests, Witnesses::Count),
+            vec!["3267: 81 40 27 has 2 solutions", "83: 17 5 has 0 solutions"]
+        );
+        assert_eq!(
+            audit(
+                &[Op::Sub, Op::Min],
+                Order::LeftToRight,
+                &tests,
+                Witnesses::First
+            ),
+            vec!["3267: 81 40 27 has no solution", "83: 17 5 has no solution"]
+        );
+        let math = Order::Precedence(Precedence::default());
+        assert_eq!(
+            audit(&part1::OPS, math, &tests, Witnesses::All),
+            vec!["3267 = 81 * 40 + 27", "83: 17 5 has no solution"]
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Lines with a `?` in place of one number: which numbers could go there?
+//! Everything left of the `?` is run forwards to see what it can come to,
+//! everything right of it backwards from the label to see what it has to
+//! come to, and the operator in front of the `?` is undone from the right to
+//! join the two up.
+
+use std::collections::BTreeSet;
+
+use super::{exec, num::Number, perms::OpPerms, Inverse, Preimage, RightInverse};
+
+/// The numbers that can fill a gap.
+#[derive(PartialEq, Debug)]
+pub enum Missing<N> {
+    /// Every number that works and isn't negative, smallest first.
+    Values(Vec<N>),
+    /// Too many to list, or some that could only be found by trying every
+    /// number there is.
+    Unlisted,
+}
+
+pub fn solve<T, N>(ops: &[T], gap: &(N, Vec<N>, usize)) -> Missing<N>
+where
+    T: Inverse<N> + RightInverse<N> + Clone,
+    N: Number,
+{
+    let (label, nums, at) = gap;
+    let (before, after) = nums.split_at(*at);
+    let Some(needed) = needed(ops, label.clone(), after) else {
+        return Missing::Unlisted;
+    };
+    if before.is_empty() {
+        return Missing::Values(Vec::from_iter(
+            needed.into_iter().filter(|n| !n.is_negative()),
+        ));
+    }
+    let mut found: BTreeSet<N> = BTreeSet::new();
+    for left in reachable(ops, before) {
+        for need in &needed {
+            for op in ops {
+                match op.unexec_right(need.clone(), left.clone()) {
+                    Preimage::None => (),
+                    Preimage::One(right) if right.is_negative() => (),
+                    Preimage::One(right) => {
+                        debug_assert!(op.exec(left.clone(), right.clone()).as_ref() == Some(need));
+                        found.insert(right);
+                    }
+                    Preimage::Any | Preimage::Unknown => return Missing::Unlisted,
+                }
+            }
+        }
+    }
+    Missing::Values(Vec::from_iter(found))
+}
+
+/// Everything `nums` can come to.
+fn reachable<T, N>(ops: &[T], nums: &[N]) -> BTreeSet<N>
+where
+    T: Inverse<N> + Clone,
+    N: Number,
+{
+    if nums.len() == 1 {
+        return BTreeSet::from([nums[0].clone()]);
+    }
+    BTreeSet::from_iter(OpPerms::new(ops, nums.len() - 1).filter_map(|ops| exec(nums, &ops)))
+}
+
+/// Everything the running total has to be just before `nums` for the line
+/// to come out to `target`, or `None` if there's no listing them.
+fn needed<T, N>(ops: &[T], target: N, nums: &[N]) -> Option<BTreeSet<N>>
+where
+    T: Inverse<N> + Clone,
+    N: Number,
+{
+    let Some((last, rest)) = nums.split_last() else {
+        return Some(BTreeSet::from([target]));
+    };
+    let mut needed: BTreeSet<N> = BTreeSet::new();
+    for op in ops {
+        match op.unexec(target.clone(), last.clone()) {
+            Preimage::None => (),
+            Preimage::One(left) => needed.extend(self::needed(ops, left, rest)?),
+            Preimage::Any | Preimage::Unknown => return None,
+        }
+    }
+    Some(needed)
+}
+
+/// Write a gap's line out with its answer, e.g.
+/// `3267: 81 ? 27 needs ? = 40 or 3159`.
+pub fn describe<N: Number>(gap: &(N, Vec<N>, usize), missing: &Missing<N>) -> String {
+    let (label, nums, at) = gap;
+    let mut operands = Vec::from_iter(nums.iter().map(|n| n.to_string()));
+    operands.insert(*at, "?".to_string());
+    let line = format!("{}: {}", label, operands.join(" "));
+    match missing {
+        Missing::Values(values) if values.is_empty() => format!("{} has no solution", line),
+        Missing::Values(values) => {
+            let values = Vec::from_iter(values.iter().map(|n| n.to_string()));
+            format!("{} needs ? = {}", line, values.join(" or "))
+        }
+        Missing::Unlisted => format!("{} works for too many values of ? to list", line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day07::{exec, parse::parse_gaps, part1, part2, perms::OpPerms, Num, Op};
+
+    use super::{describe, solve, Missing};
+
+    #[test]
+    fn test_solve() {
+        let gap = (3267u64, vec![81, 27], 1);
+        assert_eq!(solve(&part1::OPS, &gap), Missing::Values(vec![40, 3159]));
+        assert_eq!(
+            describe(&gap, &solve(&part1::OPS, &gap)),
+            "3267: 81 ? 27 needs ? = 40 or 3159"
+        );
+        assert_eq!(
+            solve(&part1::OPS, &(3267u64, vec![40, 27], 0)),
+            Missing::Values(vec![81, 3200])
+        );
+        assert_eq!(
+            solve(&part1::OPS, &(3267u64, vec![81, 40], 2)),
+            Missing::Values(vec![27, 3146])
+        );
+        assert_eq!(
+            solve(&part2::OPS, &(156u64, vec![15], 1)),
+            Missing::Values(vec![6, 141])
+        );
+        let none = (5u64, vec![3, 4], 1);
+        assert_eq!(solve(&part1::OPS, &none), Missing::Values(vec![]));
+        assert_eq!(
+            describe(&none, &solve(&part1::OPS, &none)),
+            "5: 3 ? 4 has no solution"
+        );
+        // Zero times anything is zero.
+        assert_eq!(solve(&part1::OPS, &(0u64, vec![0], 1)), Missing::Unlisted);
+        assert_eq!(
+            solve(&part1::OPS, &(0u64, vec![3, 0], 1)),
+            Missing::Unlisted
+        );
+        assert_eq!(solve(&[Op::Max], &(4u64, vec![4], 1)), Missing::Unlisted);
+        // Only `7 + -2` would do.
+        assert_eq!(
+            solve(&part1::OPS, &(5i64, vec![7], 0)),
+            Missing::Values(vec![])
+        );
+        assert_eq!(
+            solve(&part1::OPS, &(5i64, vec![7], 1)),
+            Missing::Values(vec![])
+        );
+        assert_eq!(
+            solve(&[Op::Sub], &(5i64, vec![7], 1)),
+            Missing::Values(vec![2])
+        );
+    }
+
+    /// Every value found works, and nothing small that works is missed.
+    #[test]
+    fn test_exhaustive() {
+        let ops = [Op::Add, Op::Mul, Op::Concat(10), Op::Sub, Op::Div];
+        let (_, gaps) = parse_gaps(
+            "3267: 81 ? 27
+292: 11 ? 16 20
+7290: ? 8 6 15
+12: 2 3 ?
+",
+        );
+        for gap in &gaps {
+            let Missing::Values(values) = solve(&ops, gap) else {
+                panic!("expected values for {:?}", gap);
+            };
+            let (label, nums, at) = gap;
+            for x in 0..2_000u32 {
+                let mut nums = nums.clone();
+                nums.insert(*at, Num::from(x));
+                let works = OpPerms::new(&ops, nums.len() - 1)
+                    .any(|o| exec(&nums, &o).as_ref() == Some(label));
+                assert_eq!(
+                    works,
+                    values.contains(&Num::from(x)),
+                    "{:?} with ? = {}",
+                    gap,
+                    x
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,494 @@
+//! Arithmetic on calibration numbers. Anything that would overflow gives
+//! `None` instead, which just means that way can't reach the label.
+
+use std::{
+    fmt::{self, Debug, Display},
+    hash::Hash,
+};
+
+use clap::ValueEnum;
+
+use super::Preimage;
+
+/// What kind of numbers to do the arithmetic with.
+#[derive(Clone, Copy, PartialEq, Debug, Default, ValueEnum)]
+pub enum Numbers {
+    /// Unsigned 64-bit, or as big as they come with the `bigint` feature
+    #[default]
+    Default,
+    /// Signed 64-bit, so the input can have negative numbers
+    I64,
+    /// Unsigned 128-bit
+    U128,
+    /// Integers mod 1000000007
+    Prime,
+}
+
+pub type Prime = Mod<1_000_000_007>;
+
+pub trait Number: Clone + Ord + Hash + Debug + Display + Send + Sync {
+    /// Whether the order means anything for arithmetic, e.g. that a product
+    /// of positive numbers is at least as big as either of them. Numbers
+    /// that wrap around don't, so anything that relies on it has to give up.
+    const ORDERED: bool = true;
+
+    fn from_digit(digit: u32) -> Self;
+    /// `-self`, if there is such a thing.
+    fn try_neg(&self) -> Option<Self>;
+    /// `self`, if it's small enough to use as an exponent.
+    fn to_u32(&self) -> Option<u32>;
+    fn try_add(&self, rhs: &Self) -> Option<Self>;
+    fn try_sub(&self, rhs: &Self) -> Option<Self>;
+    fn try_mul(&self, rhs: &Self) -> Option<Self>;
+    fn try_pow(&self, exp: u32) -> Option<Self>;
+    /// Every `x` for which `x ^ n == self`, where `n` is at least two.
+    fn root(&self, n: u32) -> Preimage<Self>;
+    /// `self / rhs`, if it divides exactly.
+    fn exact_div(&self, rhs: &Self) -> Option<Self>;
+    /// The smallest power of `base` with more digits than `self`, i.e. what
+    /// the left side of a concatenation gets multiplied by. `None` if that
+    /// doesn't fit, or if `self` is negative and so has no digits to speak of.
+    fn digit_shift(&self, base: u32) -> Option<Self>;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::from_digit(0)
+    }
+
+    fn is_negative(&self) -> bool {
+        *self < Self::from_digit(0)
+    }
+}
+
+macro_rules! unsigned {
+    ($t:ty) => {
+        impl Number for $t {
+            fn from_digit(digit: u32) -> Self {
+                digit as $t
+            }
+
+            fn try_neg(&self) -> Option<Self> {
+                (*self == 0).then_some(0)
+            }
+
+            fn to_u32(&self) -> Option<u32> {
+                u32::try_from(*self).ok()
+            }
+
+            fn try_add(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *rhs)
+            }
+
+            fn try_sub(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_sub(*self, *rhs)
+            }
+
+            fn try_mul(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *rhs)
+            }
+
+            fn try_pow(&self, exp: u32) -> Option<Self> {
+                <$t>::checked_pow(*self, exp)
+            }
+
+            fn root(&self, n: u32) -> Preimage<Self> {
+                // The biggest `r` with `r ^ n <= self`.
+                let (mut low, mut high): ($t, $t) = (0, 1 << (<$t>::BITS / n + 1));
+                while low < high {
+                    let mid = low + (high - low).div_ceil(2);
+                    if mid.checked_pow(n).is_some_and(|power| power <= *self) {
+                        low = mid;
+                    } else {
+                        high = mid - 1;
+                    }
+                }
+                (low.pow(n) == *self).then_some(low).into()
+            }
+
+            fn exact_div(&self, rhs: &Self) -> Option<Self> {
+                (*rhs != 0 && self.is_multiple_of(*rhs)).then(|| self / rhs)
+            }
+
+            fn digit_shift(&self, base: u32) -> Option<Self> {
+                let base = base as $t;
+                base.checked_pow(self.checked_ilog(base).unwrap_or(0) + 1)
+            }
+        }
+    };
+}
+
+unsigned!(u64);
+unsigned!(u128);
+
+impl Number for i64 {
+    fn from_digit(digit: u32) -> Self {
+        digit as i64
+    }
+
+    fn try_neg(&self) -> Option<Self> {
+        self.checked_neg()
+    }
+
+    fn to_u32(&self) -> Option<u32> {
+        u32::try_from(*self).ok()
+    }
+
+    fn try_add(&self, rhs: &Self) -> Option<Self> {
+        i64::checked_add(*self, *rhs)
+    }
+
+    fn try_sub(&self, rhs: &Self) -> Option<Self> {
+        i64::checked_sub(*self, *rhs)
+    }
+
+    fn try_mul(&self, rhs: &Self) -> Option<Self> {
+        i64::checked_mul(*self, *rhs)
+    }
+
+    fn try_pow(&self, exp: u32) -> Option<Self> {
+        i64::checked_pow(*self, exp)
+    }
+
+    fn root(&self, n: u32) -> Preimage<Self> {
+        let Preimage::One(root) = self.unsigned_abs().root(n) else {
+            return Preimage::None;
+        };
+        let root = root as i64;
+        if n % 2 == 1 {
+            Preimage::One(if *self < 0 { -root } else { root })
+        } else if *self < 0 {
+            Preimage::None
+        } else if root == 0 {
+            Preimage::One(0)
+        } else {
+            // Both `root` and `-root`.
+            Preimage::Unknown
+        }
+    }
+
+    fn exact_div(&self, rhs: &Self) -> Option<Self> {
+        (self.checked_rem(*rhs)? == 0).then(|| self.checked_div(*rhs))?
+    }
+
+    fn digit_shift(&self, base: u32) -> Option<Self> {
+        if *self < 0 {
+            return None;
+        }
+        let base = base as i64;
+        base.checked_pow(self.checked_ilog(base).unwrap_or(0) + 1)
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl Number for num_bigint::BigUint {
+    fn from_digit(digit: u32) -> Self {
+        Self::from(digit)
+    }
+
+    fn try_neg(&self) -> Option<Self> {
+        self.is_zero().then(|| self.clone())
+    }
+
+    fn to_u32(&self) -> Option<u32> {
+        u32::try_from(self).ok()
+    }
+
+    fn try_add(&self, rhs: &Self) -> Option<Self> {
+        Some(self + rhs)
+    }
+
+    fn try_sub(&self, rhs: &Self) -> Option<Self> {
+        (self >= rhs).then(|| self - rhs)
+    }
+
+    fn try_mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self * rhs)
+    }
+
+    fn try_pow(&self, exp: u32) -> Option<Self> {
+        Some(self.pow(exp))
+    }
+
+    fn root(&self, n: u32) -> Preimage<Self> {
+        let root = self.nth_root(n);
+        (root.pow(n) == *self).then_some(root).into()
+    }
+
+    fn exact_div(&self, rhs: &Self) -> Option<Self> {
+        let zero = Self::from(0u32);
+        (*rhs != zero && self % rhs == zero).then(|| self / rhs)
+    }
+
+    fn digit_shift(&self, base: u32) -> Option<Self> {
+        let ndigits = self.to_str_radix(base).len() as u32;
+        Some(Self::from(base).pow(ndigits))
+    }
+}
+
+/// Integers mod `P`. `P` has to be prime, so that anything but zero can be
+/// divided by.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Mod<const P: u64>(u64);
+
+impl<const P: u64> Mod<P> {
+    const PRIME: () = assert!(is_prime(P), "modulus has to be prime");
+
+    pub fn new(n: u64) -> Self {
+        let () = Self::PRIME;
+        Self(n % P)
+    }
+
+    fn mul(self, rhs: Self) -> Self {
+        Self((self.0 as u128 * rhs.0 as u128 % P as u128) as u64)
+    }
+
+    fn pow(self, mut exp: u64) -> Self {
+        let (mut base, mut power) = (self, Self::new(1));
+        while exp > 0 {
+            if exp % 2 == 1 {
+                power = power.mul(base);
+            }
+            base = base.mul(base);
+            exp /= 2;
+        }
+        power
+    }
+}
+
+const fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    let mut i = 2;
+    while i * i <= n {
+        if n.is_multiple_of(i) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+impl<const P: u64> Display for Mod<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<const P: u64> Number for Mod<P> {
+    const ORDERED: bool = false;
+
+    fn from_digit(digit: u32) -> Self {
+        Self::new(digit as u64)
+    }
+
+    fn try_neg(&self) -> Option<Self> {
+        Some(Self((P - self.0) % P))
+    }
+
+    fn to_u32(&self) -> Option<u32> {
+        u32::try_from(self.0).ok()
+    }
+
+    fn try_add(&self, rhs: &Self) -> Option<Self> {
+        Some(Self(((self.0 as u128 + rhs.0 as u128) % P as u128) as u64))
+    }
+
+    fn try_sub(&self, rhs: &Self) -> Option<Self> {
+        self.try_add(&rhs.try_neg()?)
+    }
+
+    fn try_mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self.mul(*rhs))
+    }
+
+    fn try_pow(&self, exp: u32) -> Option<Self> {
+        Some(self.pow(exp as u64))
+    }
+
+    fn root(&self, _n: u32) -> Preimage<Self> {
+        if self.is_zero() {
+            Preimage::One(*self)
+        } else {
+            Preimage::Unknown
+        }
+    }
+
+    fn exact_div(&self, rhs: &Self) -> Option<Self> {
+        // Fermat: `rhs ^ (P - 1) == 1`, so `rhs ^ (P - 2)` is its inverse.
+        (!rhs.is_zero()).then(|| self.mul(rhs.pow(P - 2)))
+    }
+
+    fn digit_shift(&self, base: u32) -> Option<Self> {
+        let ndigits = self.0.checked_ilog(base as u64).unwrap_or(0) + 1;
+        Some(Self::new(base as u64).pow(ndigits as u64))
+    }
+}
+
+/// Glue the digits of `right` onto the end of `left`, both written in
+/// `base`. Negative numbers don't glue.
+pub fn concat<N: Number>(left: &N, right: &N, base: u32) -> Option<N> {
+    if left.is_negative() || right.is_negative() {
+        return None;
+    }
+    if left.is_zero() {
+        return Some(right.clone());
+    }
+    left.try_mul(&right.digit_shift(base)?)?.try_add(right)
+}
+
+/// Undo `concat`: the left half of `result`, if it ends in `right`.
+pub fn split<N: Number>(result: &N, right: &N, base: u32) -> Option<N> {
+    if right.is_negative() {
+        return None;
+    }
+    let rest = result.try_sub(right).filter(|rest| !rest.is_negative())?;
+    match right.digit_shift(base) {
+        Some(shift) => rest.exact_div(&shift),
+        // Nothing fits to the left of a number that big.
+        None => rest.is_zero().then_some(rest),
+    }
+}
+
+/// Undo `concat` from the other side: the right half of `result`, if it
+/// starts with `left`. Only makes sense for ordered numbers, where there's
+/// at most one.
+pub fn strip<N: Number>(result: &N, left: &N, base: u32) -> Option<N> {
+    debug_assert!(N::ORDERED);
+    if left.is_negative() {
+        return None;
+    }
+    if left.is_zero() {
+        return (!result.is_negative()).then(|| result.clone());
+    }
+    let base_n = N::from_digit(base);
+    let mut shift = base_n.clone();
+    loop {
+        let right = result.try_sub(&left.try_mul(&shift)?)?;
+        if right.is_negative() {
+            return None;
+        }
+        if right.digit_shift(base).as_ref() == Some(&shift) {
+            return Some(right);
+        }
+        shift = shift.try_mul(&base_n)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day07::Preimage;
+
+    use super::{concat, split, strip, Mod, Number};
+
+    #[test]
+    fn test_u64() {
+        assert_eq!(0u64.digit_shift(10), Some(10));
+        assert_eq!(9u64.digit_shift(10), Some(10));
+        assert_eq!(10u64.digit_shift(10), Some(100));
+        assert_eq!(u64::MAX.digit_shift(10), None);
+        assert_eq!(5u64.digit_shift(2), Some(8));
+        assert_eq!(255u64.digit_shift(16), Some(256));
+        assert_eq!(u64::MAX.try_add(&1), None);
+        assert_eq!(12u64.exact_div(&4), Some(3));
+        assert_eq!(12u64.exact_div(&5), None);
+        assert_eq!(12u64.exact_div(&0), None);
+        assert_eq!(81u64.root(4), Preimage::One(3));
+        assert_eq!(82u64.root(4), Preimage::None);
+        assert_eq!(0u64.root(3), Preimage::One(0));
+        assert_eq!(u64::MAX.root(2), Preimage::None);
+        assert_eq!(
+            (u32::MAX as u64).pow(2).root(2),
+            Preimage::One(u32::MAX as u64)
+        );
+        assert_eq!((1u64 << 32).to_u32(), None);
+        assert_eq!(3u64.try_neg(), None);
+    }
+
+    #[test]
+    fn test_u128() {
+        let big = u64::MAX as u128 + 1;
+        assert_eq!(big.try_mul(&big), None);
+        assert_eq!(big.root(2), Preimage::One(1 << 32));
+        let odd = 3u128 << 60;
+        assert_eq!(odd.pow(2).root(2), Preimage::One(odd));
+        assert_eq!(u128::MAX.digit_shift(10), None);
+        assert_eq!(concat(&big, &0, 10), Some(big * 10));
+    }
+
+    #[test]
+    fn test_i64() {
+        assert_eq!(5i64.try_sub(&7), Some(-2));
+        assert_eq!((-12i64).exact_div(&4), Some(-3));
+        assert_eq!(i64::MIN.exact_div(&-1), None);
+        assert_eq!((-27i64).root(3), Preimage::One(-3));
+        assert_eq!(16i64.root(4), Preimage::Unknown);
+        assert_eq!((-16i64).root(4), Preimage::None);
+        assert_eq!((-3i64).digit_shift(10), None);
+        assert_eq!(concat(&12i64, &-3, 10), None);
+        assert_eq!(concat(&-12i64, &3, 10), None);
+        assert_eq!(split(&123i64, &3, 10), Some(12));
+        assert_eq!(split(&3i64, &123, 10), None);
+        assert_eq!(strip(&123i64, &12, 10), Some(3));
+        assert_eq!(strip(&123i64, &2, 10), None);
+    }
+
+    #[test]
+    fn test_mod() {
+        type M = Mod<7>;
+        let m = M::new;
+        assert_eq!(m(5).try_add(&m(4)), Some(m(2)));
+        assert_eq!(m(2).try_sub(&m(5)), Some(m(4)));
+        assert_eq!(m(3).try_mul(&m(5)), Some(m(1)));
+        assert_eq!(m(1).exact_div(&m(3)), Some(m(5)));
+        assert_eq!(m(1).exact_div(&m(0)), None);
+        assert_eq!(m(3).try_pow(6), Some(m(1)));
+        assert_eq!(m(3).try_neg(), Some(m(4)));
+        assert_eq!(m(0).try_neg(), Some(m(0)));
+        // 12 || 5 is 125, and 125 mod 7 is 6; 12 is 5 mod 7.
+        assert_eq!(concat(&m(5), &m(5), 10), Some(m(6)));
+        assert_eq!(split(&m(6), &m(5), 10), Some(m(5)));
+        assert_eq!(m(6).to_string(), "6");
+        let big = Mod::<1_000_000_007>::new(1_000_000_006);
+        assert_eq!(big.try_mul(&big), Some(Mod::new(1)));
+    }
+
+    #[test]
+    fn test_concat() {
+        assert_eq!(concat(&12u64, &345, 10), Some(12345));
+        assert_eq!(concat(&(u64::MAX / 10), &99, 10), None);
+        assert_eq!(concat(&0u64, &u64::MAX, 10), Some(u64::MAX));
+        assert_eq!(concat(&1u64, &u64::MAX, 10), None);
+        assert_eq!(concat(&0b101u64, &0b11, 2), Some(0b10111));
+        assert_eq!(concat(&0xabu64, &0xc, 16), Some(0xabc));
+        assert_eq!(split(&12345u64, &345, 10), Some(12));
+        assert_eq!(split(&12345u64, &45, 10), Some(123));
+        assert_eq!(split(&12345u64, &5, 10), Some(1234));
+        assert_eq!(split(&12345u64, &6, 10), None);
+        assert_eq!(split(&u64::MAX, &u64::MAX, 10), Some(0));
+        assert_eq!(split(&0b10111u64, &0b11, 2), Some(0b101));
+        assert_eq!(strip(&12345u64, &12, 10), Some(345));
+        assert_eq!(strip(&12345u64, &1234, 10), Some(5));
+        assert_eq!(strip(&12345u64, &13, 10), None);
+        assert_eq!(strip(&10u64, &1, 10), Some(0));
+        assert_eq!(strip(&123u64, &0, 10), Some(123));
+        assert_eq!(strip(&u64::MAX, &1, 10), Some(8446744073709551615));
+        assert_eq!(strip(&u64::MAX, &2, 10), None);
+        assert_eq!(strip(&0xabcu64, &0xa, 16), Some(0xbc));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_bigint() {
+        use num_bigint::BigUint;
+
+        let big: BigUint = "123456789012345678901234567890".parse().unwrap();
+        let small = BigUint::from(42u32);
+        let joined = concat(&big, &small, 10).unwrap();
+        assert_eq!(joined.to_string(), "12345678901234567890123456789042");
+        assert_eq!(split(&joined, &small, 10), Some(big));
+        assert_eq!(
+            BigUint::from(0u32).digit_shift(10),
+            Some(BigUint::from(10u32))
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Every operator a calibration line can use. Which ones are in play is
+//! picked at runtime, so a new variant of the puzzle is just a new list.
+
+use std::{fmt, str::FromStr};
+
+use super::{
+    num::{concat, split, strip, Number},
+    Binary, Inverse, Preimage, RightInverse,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Op {
+    Add,
+    Mul,
+    /// Gluing digits together, written in the given base.
+    Concat(u32),
+    Sub,
+    /// Division that has to come out exact.
+    Div,
+    Pow,
+    Min,
+    Max,
+}
+
+impl<N: Number> Binary<N> for Op {
+    fn exec(&self, left: N, right: N) -> Option<N> {
+        match self {
+            Op::Add => left.try_add(&right),
+            Op::Mul => left.try_mul(&right),
+            Op::Concat(base) => concat(&left, &right, *base),
+            Op::Sub => left.try_sub(&right),
+            Op::Div => left.exact_div(&right),
+            Op::Pow => pow(&left, &right),
+            Op::Min => Some(left.min(right)),
+            Op::Max => Some(left.max(right)),
+        }
+    }
+}
+
+impl<N: Number> Inverse<N> for Op {
+    fn unexec(&self, result: N, right: N) -> Preimage<N> {
+        match self {
+            Op::Add => Preimage::sub(&result, &right),
+            Op::Mul => Preimage::div(&result, &right),
+            Op::Concat(base) => split(&result, &right, *base).into(),
+            Op::Sub => result.try_add(&right).into(),
+            Op::Div if right.is_zero() => Preimage::None,
+            Op::Div => result.try_mul(&right).into(),
+            Op::Pow => root(&result, &right),
+            // The smaller side is the result, so unless it's `right`, it's
+            // `left`. If it is `right`, `left` can be anything bigger.
+            Op::Min if right > result => Preimage::One(result),
+            Op::Min if right == result => Preimage::Unknown,
+            Op::Max if right < result => Preimage::One(result),
+            Op::Max if right == result => Preimage::Unknown,
+            Op::Min | Op::Max => Preimage::None,
+        }
+    }
+}
+
+impl<N: Number> RightInverse<N> for Op {
+    fn unexec_right(&self, result: N, left: N) -> Preimage<N> {
+        match self {
+            Op::Add => Preimage::sub(&result, &left),
+            Op::Mul => Preimage::div(&result, &left),
+            // Without an order there could be a right half of every length.
+            Op::Concat(_) if !N::ORDERED => Preimage::Unknown,
+            Op::Concat(base) => strip(&result, &left, *base).into(),
+            Op::Sub => left.try_sub(&result).into(),
+            // Nothing but zero divides into zero, and anything but zero
+            // divides zero.
+            Op::Div if result.is_zero() && left.is_zero() => Preimage::Unknown,
+            Op::Div if result.is_zero() => Preimage::None,
+            Op::Div => left
+                .exact_div(&result)
+                .filter(|right| !right.is_zero())
+                .into(),
+            Op::Pow => log(&result, &left),
+            Op::Min if left > result => Preimage::One(result),
+            Op::Max if left < result => Preimage::One(result),
+            Op::Min | Op::Max if left == result => Preimage::Unknown,
+            Op::Min | Op::Max => Preimage::None,
+        }
+    }
+}
+
+fn pow<N: Number>(base: &N, exp: &N) -> Option<N> {
+    let one = N::from_digit(1);
+    match exp.to_u32() {
+        Some(exp) => base.try_pow(exp),
+        None if exp.is_negative() => None,
+        // Only these survive an exponent that big.
+        None if base.is_zero() || *base == one => Some(base.clone()),
+        None => None,
+    }
+}
+
+/// Every `base` for which `base ^ exp == result`.
+fn root<N: Number>(result: &N, exp: &N) -> Preimage<N> {
+    let one = N::from_digit(1);
+    match exp.to_u32() {
+        Some(0) if *result == one => Preimage::Any,
+        Some(0) => Preimage::None,
+        Some(1) => Preimage::One(result.clone()),
+        Some(exp) => result.root(exp),
+        None if exp.is_negative() => Preimage::None,
+        None if result.is_zero() || *result == one => Preimage::One(result.clone()),
+        None => Preimage::None,
+    }
+}
+
+/// Every `exp` for which `base ^ exp == result`.
+fn log<N: Number>(result: &N, base: &N) -> Preimage<N> {
+    let one = N::from_digit(1);
+    if base.is_zero() {
+        // Every exponent but zero gives zero.
+        return if result.is_zero() {
+            Preimage::Unknown
+        } else if *result == one {
+            Preimage::One(N::from_digit(0))
+        } else {
+            Preimage::None
+        };
+    }
+    if *base == one {
+        return if *result == one {
+            Preimage::Any
+        } else {
+            Preimage::None
+        };
+    }
+    // Counting up only finds it if powers keep getting bigger.
+    if !N::ORDERED || base.is_negative() || result.is_negative() {
+        return Preimage::Unknown;
+    }
+    let (mut power, mut exp) = (one.clone(), N::from_digit(0));
+    while power < *result {
+        let (Some(next), Some(next_exp)) = (power.try_mul(base), exp.try_add(&one)) else {
+            return Preimage::None;
+        };
+        (power, exp) = (next, next_exp);
+    }
+    if power == *result {
+        Preimage::One(exp)
+    } else {
+        Preimage::None
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Add => write!(f, "+"),
+            Op::Mul => write!(f, "*"),
+            Op::Concat(10) => write!(f, "||"),
+            Op::Concat(base) => write!(f, "||{}", base),
+            Op::Sub => write!(f, "-"),
+            Op::Div => write!(f, "/"),
+            Op::Pow => write!(f, "^"),
+            Op::Min => write!(f, "min"),
+            Op::Max => write!(f, "max"),
+        }
+    }
+}
+
+impl FromStr for Op {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+" => Ok(Op::Add),
+            "*" => Ok(Op::Mul),
+            "||" => Ok(Op::Concat(10)),
+            "-" => Ok(Op::Sub),
+            "/" => Ok(Op::Div),
+            "^" => Ok(Op::Pow),
+            "min" => Ok(Op::Min),
+            "max" => Ok(Op::Max),
+            _ => match s.strip_prefix("||").map(str::parse) {
+                Some(Ok(base @ 2..=36)) => Ok(Op::Concat(base)),
+                Some(_) => Err(format!(
+                    "concatenation base has to be 2 to 36, not '{}'",
+                    &s[2..]
+                )),
+                None => Err(format!("unknown operator '{}'", s)),
+            },
+        }
+    }
+}
+
+/// The operators to choose from, written like `+,*,||`. Solutions are
+/// searched and listed in this order.
+#[derive(Clone, PartialEq, Debug)]
+pub struct OpSet(pub Vec<Op>);
+
+impl FromStr for OpSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ops: Vec<Op> = Vec::new();
+        for op in s.split(',') {
+            let op: Op = op.trim().parse()?;
+            if ops.contains(&op) {
+                return Err(format!("'{}' is listed twice", op));
+            }
+            ops.push(op);
+        }
+        Ok(Self(ops))
+    }
+}
+
+impl fmt::Display for OpSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ops = Vec::from_iter(self.0.iter().map(|op| op.to_string()));
+        write!(f, "{}", ops.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day07::{Binary, Inverse, Preimage, RightInverse};
+
+    use super::{Op, OpSet};
+
+    #[test]
+    fn test_parse() {
+        let set: OpSet = "+, *,||,-,/,^,min,max,||2".parse().unwrap();
+        assert_eq!(set.0.len(), 9);
+        assert_eq!(set.0[8], Op::Concat(2));
+        assert_eq!(set.to_string(), "+,*,||,-,/,^,min,max,||2");
+        assert!("||1".parse::<OpSet>().is_err());
+        assert!("||x".parse::<OpSet>().is_err());
+        assert!("+,%".parse::<OpSet>().is_err());
+        assert!("+,*,+".parse::<OpSet>().is_err());
+        assert!("".parse::<OpSet>().is_err());
+    }
+
+    #[test]
+    fn test_exec() {
+        assert_eq!(Op::Sub.exec(7u64, 2), Some(5));
+        assert_eq!(Op::Sub.exec(2u64, 7), None);
+        assert_eq!(Op::Div.exec(12u64, 4), Some(3));
+        assert_eq!(Op::Div.exec(12u64, 5), None);
+        assert_eq!(Op::Pow.exec(3u64, 4), Some(81));
+        assert_eq!(Op::Pow.exec(2u64, 64), None);
+        assert_eq!(Op::Pow.exec(1u64, u64::MAX), Some(1));
+        assert_eq!(Op::Min.exec(3u64, 4), Some(3));
+        assert_eq!(Op::Max.exec(3u64, 4), Some(4));
+    }
+
+    #[test]
+    fn test_unexec() {
+        assert_eq!(Op::Sub.unexec(5u64, 2), Preimage::One(7));
+        assert_eq!(Op::Div.unexec(3u64, 4), Preimage::One(12));
+        assert_eq!(Op::Div.unexec(3u64, 0), Preimage::None);
+        assert_eq!(Op::Pow.unexec(81u64, 4), Preimage::One(3));
+        assert_eq!(Op::Pow.unexec(80u64, 4), Preimage::None);
+        assert_eq!(Op::Pow.unexec(1u64, 0), Preimage::Any);
+        assert_eq!(Op::Pow.unexec(2u64, 0), Preimage::None);
+        assert_eq!(Op::Pow.unexec(7u64, 1), Preimage::One(7));
+        assert_eq!(Op::Min.unexec(3u64, 4), Preimage::One(3));
+        assert_eq!(Op::Min.unexec(3u64, 3), Preimage::Unknown);
+        assert_eq!(Op::Min.unexec(3u64, 2), Preimage::None);
+        assert_eq!(Op::Max.unexec(4u64, 3), Preimage::One(4));
+        assert_eq!(Op::Max.unexec(4u64, 4), Preimage::Unknown);
+        assert_eq!(Op::Max.unexec(4u64, 5), Preimage::None);
+    }
+
+    #[test]
+    fn test_unexec_right() {
+        assert_eq!(Op::Add.unexec_right(7u64, 2), Preimage::One(5));
+        assert_eq!(Op::Mul.unexec_right(0u64, 0), Preimage::Any);
+        assert_eq!(Op::Concat(10).unexec_right(156u64, 15), Preimage::One(6));
+        assert_eq!(Op::Sub.unexec_right(5u64, 7), Preimage::One(2));
+        assert_eq!(Op::Sub.unexec_right(7u64, 5), Preimage::None);
+        assert_eq!(Op::Div.unexec_right(3u64, 12), Preimage::One(4));
+        assert_eq!(Op::Div.unexec_right(5u64, 12), Preimage::None);
+        assert_eq!(Op::Div.unexec_right(0u64, 0), Preimage::Unknown);
+        assert_eq!(Op::Div.unexec_right(0u64, 3), Preimage::None);
+        assert_eq!(Op::Pow.unexec_right(81u64, 3), Preimage::One(4));
+        assert_eq!(Op::Pow.unexec_right(1u64, 3), Preimage::One(0));
+        assert_eq!(Op::Pow.unexec_right(80u64, 3), Preimage::None);
+        assert_eq!(Op::Pow.unexec_right(1u64, 1), Preimage::Any);
+        assert_eq!(Op::Pow.unexec_right(0u64, 0), Preimage::Unknown);
+        assert_eq!(Op::Pow.unexec_right(u64::MAX, 2), Preimage::None);
+        assert_eq!(Op::Min.unexec_right(3u64, 4), Preimage::One(3));
+        assert_eq!(Op::Max.unexec_right(4u64, 4), Preimage::Unknown);
+        assert_eq!(Op::Max.unexec_right(4u64, 5), Preimage::None);
+    }
+}
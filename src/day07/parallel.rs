@@ -0,0 +1,65 @@
+//! Checking calibration lines on several threads. Lines vary a lot in how
+//! long they take, so instead of each thread getting a fixed share, they
+//! all take the next unchecked line until there are none left.
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+/// Every available core, or one if that can't be worked out.
+pub fn available() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// `good` for each item, in the same order as `items`, whatever order the
+/// threads get to them in.
+pub fn check<T, F>(items: &[T], threads: usize, good: F) -> Vec<bool>
+where
+    T: Sync,
+    F: Fn(&T) -> bool + Sync,
+{
+    let threads = threads.clamp(1, items.len().max(1));
+    if threads == 1 {
+        return Vec::from_iter(items.iter().map(good));
+    }
+    let next = AtomicUsize::new(0);
+    let mut results = vec![false; items.len()];
+    thread::scope(|s| {
+        let workers = Vec::from_iter((0..threads).map(|_| {
+            s.spawn(|| {
+                let mut passed: Vec<usize> = Vec::new();
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= items.len() {
+                        return passed;
+                    }
+                    if good(&items[i]) {
+                        passed.push(i);
+                    }
+                }
+            })
+        }));
+        for worker in workers {
+            for i in worker.join().unwrap() {
+                results[i] = true;
+            }
+        }
+    });
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+
+    #[test]
+    fn test_check() {
+        let items = Vec::from_iter(0..1000u32);
+        let expected = Vec::from_iter(items.iter().map(|i| i % 3 == 0));
+        for threads in [0, 1, 2, 7, 2000] {
+            assert_eq!(check(&items, threads, |i| i % 3 == 0), expected);
+        }
+        assert!(check(&[] as &[u32], 4, |_| true).is_empty());
+    }
+}
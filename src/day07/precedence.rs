@@ -0,0 +1,304 @@
+//! Evaluating with the usual rules, where `*` happens before `+`. Working
+//! backwards no longer splits the line neatly at the last number, so this
+//! searches forwards, keeping a stack of everything still waiting on an
+//! operator that binds tighter.
+
+use std::{cmp::Ordering, fmt, ops::ControlFlow};
+
+use clap::ValueEnum;
+
+use super::{num::Number, solve, Binary, Op};
+
+/// Where `||` sits among the other operators.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, ValueEnum)]
+pub enum ConcatBinds {
+    /// After everything else
+    Loosest,
+    /// Alongside `+` and `-`
+    Sum,
+    /// Alongside `*` and `/`
+    Product,
+    /// Before everything else
+    #[default]
+    Tightest,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Precedence {
+    pub concat: ConcatBinds,
+}
+
+impl Precedence {
+    /// Higher binds tighter. Operators on the same level go left to right,
+    /// except `^`, which goes right to left as in `2 ^ 3 ^ 2 = 2 ^ 9`.
+    fn level(&self, op: &Op) -> u8 {
+        match op {
+            Op::Min | Op::Max => 1,
+            Op::Add | Op::Sub => 2,
+            Op::Mul | Op::Div => 3,
+            Op::Pow => 4,
+            Op::Concat(_) => match self.concat {
+                ConcatBinds::Loosest => 0,
+                ConcatBinds::Sum => 2,
+                ConcatBinds::Product => 3,
+                ConcatBinds::Tightest => 5,
+            },
+        }
+    }
+
+    /// Whether `top`, already on the stack, has to be done before `op`.
+    fn before(&self, top: &Op, op: &Op) -> bool {
+        match self.level(top).cmp(&self.level(op)) {
+            Ordering::Greater => true,
+            Ordering::Equal => *op != Op::Pow,
+            Ordering::Less => false,
+        }
+    }
+}
+
+/// How the operators in a line are grouped.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Order {
+    LeftToRight,
+    Precedence(Precedence),
+}
+
+impl Order {
+    /// Whether some choice of operators makes the numbers come out to the
+    /// label.
+    pub fn good<N: Number>(&self, ops: &[Op], test: &(N, Vec<N>)) -> bool {
+        let (label, nums) = test;
+        self.witness(ops, label.clone(), nums).is_some()
+    }
+
+    pub fn witness<N: Number>(&self, ops: &[Op], target: N, nums: &[N]) -> Option<Vec<Op>> {
+        match self {
+            Order::LeftToRight => solve::witness(ops, target, nums),
+            Order::Precedence(prec) => {
+                let mut found: Option<Vec<Op>> = None;
+                let _ = prec.search(ops, &target, nums, |ops| {
+                    found = Some(ops);
+                    ControlFlow::Break(())
+                });
+                found
+            }
+        }
+    }
+
+    pub fn witnesses<N: Number>(&self, ops: &[Op], target: N, nums: &[N]) -> Vec<Vec<Op>> {
+        match self {
+            Order::LeftToRight => solve::witnesses(ops, target, nums),
+            Order::Precedence(prec) => {
+                let mut found: Vec<Vec<Op>> = Vec::new();
+                let _ = prec.search(ops, &target, nums, |ops| {
+                    found.push(ops);
+                    ControlFlow::Continue(())
+                });
+                found
+            }
+        }
+    }
+
+    pub fn count<N: Number>(&self, ops: &[Op], target: N, nums: &[N]) -> u128 {
+        match self {
+            Order::LeftToRight => solve::count(ops, target, nums),
+            Order::Precedence(_) => self.witnesses(ops, target, nums).len() as u128,
+        }
+    }
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Order::LeftToRight => write!(f, "left to right"),
+            Order::Precedence(prec) => {
+                let concat = prec.concat.to_possible_value().unwrap();
+                write!(f, "by precedence, || {}", concat.get_name())
+            }
+        }
+    }
+}
+
+/// Numbers still waiting to be combined. Every operator on the stack binds
+/// looser than the one above it, or is a `^` under another `^`, so none of
+/// them can go until something looser turns up, or the line ends.
+#[derive(Clone)]
+struct Stack<N> {
+    nums: Vec<N>,
+    ops: Vec<Op>,
+}
+
+impl<N: Number> Stack<N> {
+    fn new(first: N) -> Self {
+        Self {
+            nums: vec![first],
+            ops: Vec::new(),
+        }
+    }
+
+    /// Combine the top two numbers, or `None` if that overflows.
+    fn reduce(&mut self) -> Option<()> {
+        let op = self.ops.pop().unwrap();
+        let right = self.nums.pop().unwrap();
+        let left = self.nums.pop().unwrap();
+        self.nums.push(op.exec(left, right)?);
+        Some(())
+    }
+
+    fn push(&mut self, prec: &Precedence, op: Op, num: N) -> Option<()> {
+        while let Some(top) = self.ops.last() {
+            if !prec.before(top, &op) {
+                break;
+            }
+            self.reduce()?;
+        }
+        self.ops.push(op);
+        self.nums.push(num);
+        Some(())
+    }
+
+    fn finish(mut self) -> Option<N> {
+        while !self.ops.is_empty() {
+            self.reduce()?;
+        }
+        self.nums.pop()
+    }
+}
+
+impl Precedence {
+    /// What the line comes to, or `None` if anything overflows.
+    #[cfg(test)]
+    pub fn eval<N: Number>(&self, nums: &[N], ops: &[Op]) -> Option<N> {
+        assert_eq!(nums.len(), ops.len() + 1);
+        let mut stack = Stack::new(nums[0].clone());
+        for (op, num) in ops.iter().zip(&nums[1..]) {
+            stack.push(self, *op, num.clone())?;
+        }
+        stack.finish()
+    }
+
+    fn search<N, F>(&self, ops: &[Op], target: &N, nums: &[N], mut f: F) -> ControlFlow<()>
+    where
+        N: Number,
+        F: FnMut(Vec<Op>) -> ControlFlow<()>,
+    {
+        let (first, rest) = nums.split_first().unwrap();
+        self.extend(
+            ops,
+            target,
+            rest,
+            Stack::new(first.clone()),
+            &mut Vec::new(),
+            &mut f,
+        )
+    }
+
+    /// Try every operator in front of the next number, dropping any choice
+    /// that overflows before the line is done.
+    fn extend<N, F>(
+        &self,
+        ops: &[Op],
+        target: &N,
+        nums: &[N],
+        stack: Stack<N>,
+        chosen: &mut Vec<Op>,
+        f: &mut F,
+    ) -> ControlFlow<()>
+    where
+        N: Number,
+        F: FnMut(Vec<Op>) -> ControlFlow<()>,
+    {
+        let Some((next, rest)) = nums.split_first() else {
+            if stack.finish().as_ref() == Some(target) {
+                return f(chosen.clone());
+            }
+            return ControlFlow::Continue(());
+        };
+        for op in ops {
+            let mut stack = stack.clone();
+            if stack.push(self, *op, next.clone()).is_none() {
+                continue;
+            }
+            chosen.push(*op);
+            let flow = self.extend(ops, target, rest, stack, chosen, f);
+            chosen.pop();
+            flow?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day07::{
+        parse::parse, part1, part2, perms::OpPerms, tests::EXAMPLE, Op, OpSet, Test,
+    };
+
+    use super::{ConcatBinds, Order, Precedence};
+
+    fn prec(concat: ConcatBinds) -> Precedence {
+        Precedence { concat }
+    }
+
+    #[test]
+    fn test_eval() {
+        let math = Precedence::default();
+        assert_eq!(math.eval(&[2u64, 3, 4], &[Op::Add, Op::Mul]), Some(14));
+        assert_eq!(math.eval(&[2u64, 3, 4], &[Op::Mul, Op::Add]), Some(10));
+        assert_eq!(math.eval(&[2u64, 3, 2], &[Op::Pow, Op::Pow]), Some(512));
+        assert_eq!(math.eval(&[2u64, 3, 2], &[Op::Pow, Op::Mul]), Some(16));
+        assert_eq!(math.eval(&[2u64, 2, 3], &[Op::Mul, Op::Pow]), Some(16));
+        assert_eq!(math.eval(&[9u64, 2, 3], &[Op::Sub, Op::Mul]), Some(3));
+        assert_eq!(math.eval(&[1u64, 2, 3], &[Op::Sub, Op::Mul]), None);
+        assert_eq!(math.eval(&[7u64], &[]), Some(7));
+
+        let nums = [1u64, 2, 3];
+        let ops = [Op::Add, Op::Concat(10)];
+        assert_eq!(prec(ConcatBinds::Tightest).eval(&nums, &ops), Some(24));
+        assert_eq!(prec(ConcatBinds::Loosest).eval(&nums, &ops), Some(33));
+        assert_eq!(prec(ConcatBinds::Sum).eval(&nums, &ops), Some(33));
+        let ops = [Op::Concat(10), Op::Mul];
+        assert_eq!(prec(ConcatBinds::Product).eval(&nums, &ops), Some(36));
+        assert_eq!(prec(ConcatBinds::Loosest).eval(&nums, &ops), Some(16));
+    }
+
+    #[test]
+    fn test_witness() {
+        let math = Order::Precedence(Precedence::default());
+        assert_eq!(
+            math.witnesses(&part1::OPS, 3267u64, &[81, 40, 27]),
+            vec![vec![Op::Mul, Op::Add]]
+        );
+        assert_eq!(
+            math.witness(&part1::OPS, 1161u64, &[81, 40, 27]),
+            Some(vec![Op::Add, Op::Mul])
+        );
+        assert_eq!(math.witness(&part1::OPS, 3267u64, &[3267]), Some(vec![]));
+        assert_eq!(
+            Order::LeftToRight.witness(&part1::OPS, 1161u64, &[81, 40, 27]),
+            None
+        );
+        // Multiplying by zero at the end only clears its own product now.
+        assert_eq!(math.count(&part1::OPS, 0u64, &[3, 4, 5, 0]), 1);
+    }
+
+    /// Searching agrees with evaluating every choice of operators.
+    #[test]
+    fn test_brute() {
+        let set: OpSet = "+,*,||,-,/,^".parse().unwrap();
+        let tests: Vec<Test> = parse(EXAMPLE);
+        for concat in [ConcatBinds::Loosest, ConcatBinds::Product] {
+            let prec = prec(concat);
+            let order = Order::Precedence(prec);
+            for ops in [&part1::OPS[..], &part2::OPS[..], &set.0[..]] {
+                for (label, nums) in tests.iter().cloned() {
+                    let all = Vec::from_iter(
+                        OpPerms::new(ops, nums.len() - 1)
+                            .filter(|o| prec.eval(&nums, o).as_ref() == Some(&label)),
+                    );
+                    assert_eq!(order.witnesses(ops, label, &nums), all);
+                }
+            }
+        }
+    }
+}
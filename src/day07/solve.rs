@@ -0,0 +1,225 @@
+use std::ops::ControlFlow;
+
+use super::{exec, perms::OpPerms, Inverse, Preimage};
+
+/// One choice of operators that makes the numbers come out to `target`.
+pub fn witness<T, N>(ops: &[T], target: N, nums: &[N]) -> Option<Vec<T>>
+where
+    T: Inverse<N> + Clone,
+    N: PartialEq + Clone,
+{
+    let mut found: Option<Vec<T>> = None;
+    let _ = search(ops, target, nums, &mut Vec::new(), &mut |ops| {
+        found = Some(ops);
+        ControlFlow::Break(())
+    });
+    found
+}
+
+/// Every choice of operators that makes the numbers come out to `target`.
+pub fn witnesses<T, N>(ops: &[T], target: N, nums: &[N]) -> Vec<Vec<T>>
+where
+    T: Inverse<N> + Clone,
+    N: PartialEq + Clone,
+{
+    let mut found: Vec<Vec<T>> = Vec::new();
+    let _ = search(ops, target, nums, &mut Vec::new(), &mut |ops| {
+        found.push(ops);
+        ControlFlow::Continue(())
+    });
+    found
+}
+
+/// How many choices of operators make the numbers come out to `target`,
+/// without listing them.
+pub fn count<T, N>(ops: &[T], target: N, nums: &[N]) -> u128
+where
+    T: Inverse<N> + Clone,
+    N: PartialEq + Clone,
+{
+    let (last, rest) = nums.split_last().unwrap();
+    if rest.is_empty() {
+        return (*last == target) as u128;
+    }
+    let mut total: u128 = 0;
+    for op in ops {
+        total += match op.unexec(target.clone(), last.clone()) {
+            Preimage::None => 0,
+            Preimage::One(left) => count(ops, left, rest),
+            Preimage::Any => prefixes(ops, rest).len() as u128,
+            Preimage::Unknown => forwards(ops, op, &target, rest, last).len() as u128,
+        };
+    }
+    total
+}
+
+/// Every choice of operators for `nums` that doesn't overflow. Used when
+/// whatever they come to will do, but they still have to come to something.
+fn prefixes<T, N>(ops: &[T], nums: &[N]) -> Vec<Vec<T>>
+where
+    T: Inverse<N> + Clone,
+    N: Clone,
+{
+    if nums.len() == 1 {
+        return vec![vec![]];
+    }
+    Vec::from_iter(OpPerms::new(ops, nums.len() - 1).filter(|ops| exec(nums, ops).is_some()))
+}
+
+/// Every choice of operators for `rest` that `op` then takes to `target`,
+/// found the slow way for when `op` can't be run backwards.
+fn forwards<T, N>(ops: &[T], op: &T, target: &N, rest: &[N], last: &N) -> Vec<Vec<T>>
+where
+    T: Inverse<N> + Clone,
+    N: PartialEq + Clone,
+{
+    if rest.len() == 1 {
+        let hit = op.exec(rest[0].clone(), last.clone()).as_ref() == Some(target);
+        return if hit { vec![vec![]] } else { vec![] };
+    }
+    Vec::from_iter(OpPerms::new(ops, rest.len() - 1).filter(|ops| {
+        exec(rest, ops)
+            .and_then(|left| op.exec(left, last.clone()))
+            .as_ref()
+            == Some(target)
+    }))
+}
+
+/// Work backwards from the last number: every operator that could have
+/// produced `target` from it leaves a smaller problem for the rest, and an
+/// operator that can't leaves nothing to search. `suffix` holds the
+/// operators already chosen for the numbers to the right, last first.
+fn search<T, N, F>(
+    ops: &[T],
+    target: N,
+    nums: &[N],
+    suffix: &mut Vec<T>,
+    f: &mut F,
+) -> ControlFlow<()>
+where
+    T: Inverse<N> + Clone,
+    N: PartialEq + Clone,
+    F: FnMut(Vec<T>) -> ControlFlow<()>,
+{
+    let (last, rest) = nums.split_last().unwrap();
+    if rest.is_empty() {
+        if *last == target {
+            return f(Vec::from_iter(suffix.iter().rev().cloned()));
+        }
+        return ControlFlow::Continue(());
+    }
+    for op in ops {
+        let lefts = match op.unexec(target.clone(), last.clone()) {
+            Preimage::None => continue,
+            Preimage::One(left) => {
+                debug_assert!(op.exec(left.clone(), last.clone()).as_ref() == Some(&target));
+                suffix.push(op.clone());
+                let flow = search(ops, left, rest, suffix, f);
+                suffix.pop();
+                flow?;
+                continue;
+            }
+            Preimage::Any => prefixes(ops, rest),
+            Preimage::Unknown => forwards(ops, op, &target, rest, last),
+        };
+        suffix.push(op.clone());
+        for mut prefix in lefts {
+            prefix.extend(suffix.iter().rev().cloned());
+            if f(prefix).is_break() {
+                suffix.pop();
+                return ControlFlow::Break(());
+            }
+        }
+        suffix.pop();
+    }
+    ControlFlow::Continue(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day07::{good_brute, part1, part2, perms::OpPerms, Op, OpSet};
+
+    use super::{count, witness, witnesses};
+
+    #[test]
+    fn test_witness() {
+        let ops = &part1::OPS;
+        assert_eq!(
+            witness(ops, 3267u64, &[81, 40, 27]),
+            Some(vec![Op::Mul, Op::Add])
+        );
+        assert_eq!(
+            witness(&part2::OPS, 7290u64, &[6, 8, 6, 15]).unwrap().len(),
+            3
+        );
+        assert_eq!(witness(ops, 7290u64, &[6, 8, 6, 15]), None);
+        assert_eq!(witness(ops, 5u64, &[5]), Some(vec![]));
+        assert_eq!(witness(ops, 3u64, &[2, 0, 1]), Some(vec![Op::Add, Op::Add]));
+        assert_eq!(witness(ops, 4u64, &[2, 0, 1]), None);
+    }
+
+    #[test]
+    fn test_witnesses() {
+        let ops = &part1::OPS;
+        assert_eq!(
+            witnesses(ops, 3267u64, &[81, 40, 27]),
+            vec![vec![Op::Mul, Op::Add], vec![Op::Add, Op::Mul]]
+        );
+        assert_eq!(count(ops, 3267u64, &[81, 40, 27]), 2);
+
+        // Multiplying by zero at the end makes everything before it work.
+        let all = witnesses(ops, 0u64, &[3, 4, 5, 0]);
+        assert_eq!(all.len(), 4);
+        assert!(all.iter().all(|ops| ops[2] == Op::Mul));
+        assert_eq!(count(ops, 0u64, &[3, 4, 5, 0]), 4);
+        assert_eq!(count(&part2::OPS, 0u64, &[3, 4, 5, 0]), 9);
+        assert_eq!(witnesses(&part2::OPS, 0u64, &[3, 4, 5, 0]).len(), 9);
+
+        // ...as long as everything before it fits.
+        let big = u64::MAX / 2;
+        assert_eq!(
+            witnesses(ops, 0u64, &[big, 3, 0]),
+            vec![vec![Op::Add, Op::Mul]]
+        );
+        assert_eq!(count(ops, 0u64, &[big, 3, 0]), 1);
+        assert_eq!(count(ops, 0u64, &[big, big, 3, 0]), 0);
+    }
+
+    #[test]
+    fn test_op_sets() {
+        let set: OpSet = "+,*,||,-,/,^,min,max".parse().unwrap();
+        let ops = set.0.as_slice();
+        assert_eq!(
+            witness(&[Op::Sub, Op::Pow], 9u64, &[5, 2, 2]),
+            Some(vec![Op::Sub, Op::Pow])
+        );
+        assert_eq!(
+            witness(&[Op::Div, Op::Max], 7u64, &[12, 4, 7]),
+            Some(vec![Op::Div, Op::Max])
+        );
+        // `max` with the right side equal to the target can't be undone, so
+        // whatever comes before it gets checked forwards.
+        assert_eq!(
+            witnesses(&[Op::Add, Op::Max], 7u64, &[1, 2, 7]),
+            vec![vec![Op::Add, Op::Max], vec![Op::Max, Op::Max]]
+        );
+        for (target, nums) in [
+            (7u64, vec![1, 2, 7]),
+            (3, vec![2, 5, 3, 1]),
+            (16, vec![2, 2, 2]),
+            (0, vec![4, 4, 9, 0]),
+            (10, vec![100, 3, 10]),
+        ] {
+            let all = Vec::from_iter(
+                OpPerms::new(ops, nums.len() - 1)
+                    .filter(|o| crate::day07::exec(&nums, o) == Some(target)),
+            );
+            assert_eq!(witnesses(ops, target, &nums).len(), all.len());
+            assert_eq!(count(ops, target, &nums), all.len() as u128);
+            assert_eq!(
+                witness(ops, target, &nums).is_some(),
+                good_brute(ops, &(target, nums.clone()))
+            );
+        }
+    }
+}
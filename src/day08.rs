@@ -1,14 +1,189 @@
-use std::fs;
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::PathBuf,
+};
 
-pub fn day08() {
+pub use node::AntiNode;
+use provenance::Provenance;
+use render::Overlay;
+pub use resonance::{Ratio, Resonance};
+
+#[derive(clap::Args)]
+#[group(id = "day08")]
+pub struct Options {
+    /// Count antinodes at these distance ratios instead of doing parts 1 and
+    /// 2, e.g. `3:1,3:2`
+    #[arg(long, value_name = "FAR:NEAR", value_delimiter = ',')]
+    pub ratios: Vec<Ratio>,
+
+    /// Count every point in line with two antennas instead of doing parts 1
+    /// and 2
+    #[arg(long)]
+    pub harmonics: bool,
+
+    /// With `--ratios` or `--harmonics`, also count points between the two
+    /// antennas
+    #[arg(long)]
+    pub interior: bool,
+
+    /// With `--ratios` or `--harmonics`, only count points at most this many
+    /// steps from the nearer antenna
+    #[arg(long, value_name = "K")]
+    pub steps: Option<u32>,
+
+    /// With `--ratios` or `--harmonics`, let `#` squares block the line
+    /// between two antennas and the antinodes past them
+    #[arg(long)]
+    pub blocking: bool,
+
+    /// Print the map with `#` for each antinode and `%` for each obstacle
+    #[arg(long)]
+    pub overlay: bool,
+
+    /// Color the `--overlay` map by frequency
+    #[arg(long)]
+    pub color: bool,
+
+    /// Write an SVG picture of the antennas, their lines, and the part 2
+    /// antinodes (or those from `--ratios`/`--harmonics`)
+    #[arg(long, value_name = "PATH")]
+    pub svg: Option<PathBuf>,
+
+    /// Print how many antennas, pairs and antinodes each frequency has, for
+    /// part 2 (or `--ratios`/`--harmonics`)
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Print which antenna pairs put an antinode at this position, for part 2
+    /// (or `--ratios`/`--harmonics`)
+    #[arg(long, value_name = "X,Y")]
+    pub at: Vec<AntiNode>,
+
+    /// Read the input as 2D slices of a 3D volume, with a blank line between
+    /// each, and only count antinodes
+    #[arg(long)]
+    pub volume: bool,
+}
+
+impl Options {
+    fn resonance(&self) -> Option<Resonance> {
+        (!self.ratios.is_empty() || self.harmonics).then(|| Resonance {
+            ratios: self.ratios.clone(),
+            harmonics: self.harmonics,
+            interior: self.interior,
+            steps: self.steps,
+            blocking: self.blocking,
+        })
+    }
+}
+
+pub fn day08(options: &Options) {
     let input = fs::read_to_string("day08_input.txt").unwrap();
+    if options.volume {
+        count_volume(options, &input);
+        return;
+    }
+    if let Some(resonance) = options.resonance() {
+        let (nodes, space) = parse::parse_space(&input);
+        let antinodes = resonance.antinodes(&nodes, &space);
+        println!("antinodes: {}", antinodes.len());
+        draw(options, &input, &resonance, true);
+        explain(options, &input, &resonance);
+        return;
+    }
     let sum = part1::part1(&input).len();
     println!("part 1: {}", sum);
+    draw(options, &input, &Resonance::part1(), false);
     let sum = part2::part2(&input).len();
     println!("part 2: {}", sum);
+    draw(options, &input, &Resonance::part2(), true);
+    explain(options, &input, &Resonance::part2());
+}
+
+fn count_volume(options: &Options, input: &str) {
+    let (nodes, space) = parse::parse_volume(input);
+    if let Some(resonance) = options.resonance() {
+        let antinodes = resonance.antinodes(&nodes, &space);
+        println!("antinodes: {}", antinodes.len());
+        return;
+    }
+    let sum = Resonance::part1().antinodes(&nodes, &space).len();
+    println!("part 1: {}", sum);
+    let sum = Resonance::part2().antinodes(&nodes, &space).len();
+    println!("part 2: {}", sum);
+}
+
+/// Show the antinodes however the options ask for, only writing the SVG if
+/// `svg` is set.
+fn draw(options: &Options, input: &str, resonance: &Resonance, svg: bool) {
+    let svg = options.svg.as_ref().filter(|_| svg);
+    if !options.overlay && svg.is_none() {
+        return;
+    }
+    let (nodes, space) = parse::parse_space(input);
+    let overlay = Overlay::new(&nodes, &space, resonance);
+    if options.overlay {
+        print!("{}", overlay.text(options.color));
+    }
+    if let Some(path) = svg {
+        overlay
+            .write_svg(&mut BufWriter::new(File::create(path).unwrap()))
+            .unwrap();
+    }
+}
+
+/// Print where antinodes came from, if the options ask.
+fn explain(options: &Options, input: &str, resonance: &Resonance) {
+    if !options.stats && options.at.is_empty() {
+        return;
+    }
+    let (nodes, space) = parse::parse_space(input);
+    let provenance = Provenance::new(resonance, &nodes, &space);
+    if options.stats {
+        for stats in provenance.stats() {
+            let overlaps = Vec::from_iter(
+                stats
+                    .overlaps
+                    .iter()
+                    .map(|(code, n)| format!("{} with {}", n, code)),
+            );
+            println!(
+                "frequency {}: {} antennas, {} pairs, {} antinodes, shared: {}",
+                stats.code,
+                stats.antennas,
+                stats.pairs,
+                stats.antinodes,
+                if overlaps.is_empty() {
+                    "none".to_string()
+                } else {
+                    overlaps.join(", ")
+                }
+            );
+        }
+        println!("antinodes in all: {}", provenance.count());
+    }
+    for pos in &options.at {
+        let pairs = provenance.at(*pos);
+        if pairs.is_empty() {
+            println!("no antinode at ({}, {})", pos.x(), pos.y());
+        }
+        for pair in pairs {
+            println!(
+                "antinode at ({}, {}) from {} at ({}, {}) and ({}, {})",
+                pos.x(),
+                pos.y(),
+                pair.code,
+                pair.a.x(),
+                pair.a.y(),
+                pair.b.x(),
+                pair.b.y()
+            );
+        }
+    }
 }
 
-mod vec2;
+mod vector;
 
 mod node;
 
@@ -18,6 +193,14 @@ mod part1;
 
 mod part2;
 
+mod resonance;
+
+mod render;
+
+mod provenance;
+
+mod space;
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
@@ -27,9 +210,7 @@ mod tests {
         part2::{self},
     };
 
-    #[test]
-    fn test_sample() {
-        let input = "............
+    pub const EXAMPLE: &str = "............
 ........0...
 .....0......
 .......0....
@@ -42,6 +223,10 @@ mod tests {
 ............
 ............
 ";
+
+    #[test]
+    fn test_sample() {
+        let input = EXAMPLE;
         let antinodes = part1::part1(input);
         let poslist: HashSet<[u16; 2]> = HashSet::from([
             [6, 0],
@@ -60,10 +245,24 @@ mod tests {
             [6, 5],
         ]);
         assert_eq!(poslist.len(), 14);
+        assert_eq!(HashSet::from_iter(antinodes.iter().map(|a| a.pos)), poslist);
+        assert_eq!(part2::part2(input).len(), 34);
+    }
+
+    /// The step between antennas two apart gets cut down to one, so the
+    /// point between them counts too.
+    #[test]
+    fn test_lattice() {
+        let input = "a....
+.....
+.....
+.....
+..a..
+";
+        let antinodes = part2::part2(input);
         assert_eq!(
-            HashSet::from_iter(antinodes.iter().map(|a| [a.x, a.y])),
-            poslist
+            HashSet::from_iter(antinodes.iter().map(|a| a.pos)),
+            HashSet::from([[0, 0], [1, 2], [2, 4]])
         );
-        assert_eq!(part2::part2(input).len(), 34);
     }
 }
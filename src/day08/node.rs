@@ -1,45 +1,68 @@
-use super::vec2::Vec2;
+use std::str::FromStr;
+
+use super::vector::Vector;
 
 #[derive(PartialEq, Debug)]
-pub struct Node {
+pub struct Node<const D: usize = 2> {
     pub code: char,
-    pub pos: Vec2,
+    pub pos: Vector<D>,
 }
 
 impl Node {
     #[inline]
     pub fn new<T>(code: char, x: T, y: T) -> Self
     where
-        T: Into<f64>,
+        T: Into<i32>,
     {
         Self {
             code,
-            pos: Vec2::new(x.into(), y.into()),
+            pos: Vector([x.into(), y.into()]),
         }
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
-pub struct AntiNode {
-    pub x: u16,
-    pub y: u16,
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct AntiNode<const D: usize = 2> {
+    pub pos: [u16; D],
 }
 
 impl AntiNode {
     #[inline]
     pub fn new(x: u16, y: u16) -> Self {
-        Self { x, y }
+        Self { pos: [x, y] }
+    }
+
+    #[inline]
+    pub fn x(self) -> u16 {
+        self.pos[0]
+    }
+
+    #[inline]
+    pub fn y(self) -> u16 {
+        self.pos[1]
     }
-    pub fn from_vec2(pos: Vec2, width: u16, height: u16) -> Option<Self> {
-        let x = pos.x.round();
-        let y = pos.y.round();
-        if x >= 0.0 && x < (width as f64) && y >= 0.0 && y < (height as f64) {
-            Some(Self {
-                x: x as u16,
-                y: y as u16,
-            })
-        } else {
-            None
+}
+
+impl<const D: usize> AntiNode<D> {
+    /// The antinode at `pos`, if that's inside a map or volume of `size`.
+    pub fn from_vector(pos: Vector<D>, size: [u16; D]) -> Option<Self> {
+        let mut inside = [0; D];
+        for i in 0..D {
+            inside[i] = u16::try_from(pos.0[i]).ok().filter(|n| *n < size[i])?;
         }
+        Some(Self { pos: inside })
+    }
+}
+
+impl FromStr for AntiNode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (x, y) = s
+            .split_once(',')
+            .ok_or_else(|| format!("expected X,Y, got '{}'", s))?;
+        let x: u16 = x.trim().parse().map_err(|e| format!("bad x: {}", e))?;
+        let y: u16 = y.trim().parse().map_err(|e| format!("bad y: {}", e))?;
+        Ok(Self::new(x, y))
     }
 }
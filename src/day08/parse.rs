@@ -1,26 +1,91 @@
-use std::{iter::Peekable, str::Chars};
+use std::{collections::HashSet, iter::Peekable, str::Chars};
 
-use super::node::Node;
+use super::{node::Node, space::Space, vector::Vector};
+
+/// Anything but an empty square.
+enum Elem {
+    Node(Node),
+    Obstacle(Vector<2>),
+}
+
+/// Antennas and map size, without the obstacles.
+pub fn parse(s: &str) -> (Vec<Node>, u16, u16) {
+    let (nodes, space) = parse_space(s);
+    (nodes, space.width(), space.height())
+}
 
 /// world: line+ trailer
 /// line: elem+ '\n'
-/// elem: '.' | code
+/// elem: '.' | '#' | code
 /// trailer: '\n'*
-pub fn parse(s: &str) -> (Vec<Node>, u16, u16) {
+pub fn parse_space(s: &str) -> (Vec<Node>, Space) {
     let width = s.lines().next().unwrap().chars().count() as u16;
     let mut s = s.chars().peekable();
-    let mut nodes: Vec<Node> = Vec::from_iter(line(&mut s, 0).drain(0..).flatten());
-    let mut y = 1;
-    while s.peek().map(|c| *c != '\n').unwrap_or(false) {
-        nodes.extend(line(&mut s, y).drain(0..).flatten());
+    let mut space = Space::open([width, 0]);
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut y = 0;
+    while y == 0 || s.peek().map(|c| *c != '\n').unwrap_or(false) {
+        for elem in line(&mut s, y).drain(0..).flatten() {
+            match elem {
+                Elem::Node(node) => nodes.push(node),
+                Elem::Obstacle(pos) => _ = space.obstacles.insert(pos),
+            }
+        }
         y += 1;
     }
     trailer(&mut s);
-    (nodes, width, y)
+    space.size[1] = y;
+    (nodes, space)
+}
+
+/// The same, but with 2D slices stacked up into a volume, one after another
+/// with a blank line between each:
+///
+/// volume: slice ('\n' slice)* trailer
+/// slice: line+
+pub fn parse_volume(s: &str) -> (Vec<Node<3>>, Space<3>) {
+    let width = s.lines().next().unwrap().chars().count() as u16;
+    let mut s = s.chars().peekable();
+    let mut nodes: Vec<Node<3>> = Vec::new();
+    let mut obstacles: HashSet<Vector<3>> = HashSet::new();
+    let mut height: Option<u16> = None;
+    let mut z = 0;
+    loop {
+        let mut y = 0;
+        while s.peek().map(|c| *c != '\n').unwrap_or(false) {
+            let lift = |pos: Vector<2>| Vector([pos.x(), pos.y(), z as i32]);
+            for elem in line(&mut s, y).drain(0..).flatten() {
+                match elem {
+                    Elem::Node(node) => nodes.push(Node {
+                        code: node.code,
+                        pos: lift(node.pos),
+                    }),
+                    Elem::Obstacle(pos) => _ = obstacles.insert(lift(pos)),
+                }
+            }
+            y += 1;
+        }
+        assert_ne!(y, 0, "empty slice");
+        assert_eq!(*height.get_or_insert(y), y, "slices have different heights");
+        z += 1;
+        // Another slice after a blank line, or the trailer.
+        let mut next = s.clone();
+        next.next();
+        if next.peek().map(|c| *c == '\n').unwrap_or(true) {
+            break;
+        }
+        s.next();
+    }
+    trailer(&mut s);
+    let space = Space {
+        size: [width, height.unwrap(), z],
+        obstacles,
+    };
+    (nodes, space)
 }
 
-fn line(s: &mut Peekable<Chars<'_>>, y: u16) -> Vec<Option<Node>> {
-    let mut nodes: Vec<Option<Node>> = vec![elem(s, 0, y)];
+fn line(s: &mut Peekable<Chars<'_>>, y: u16) -> Vec<Option<Elem>> {
+    let mut nodes: Vec<Option<Elem>> = vec![elem(s, 0, y)];
     let mut x = 1;
     while s.peek() != Some(&'\n') {
         nodes.push(elem(s, x, y));
@@ -30,11 +95,13 @@ fn line(s: &mut Peekable<Chars<'_>>, y: u16) -> Vec<Option<Node>> {
     nodes
 }
 
-fn elem(s: &mut Peekable<Chars<'_>>, x: u16, y: u16) -> Option<Node> {
+fn elem(s: &mut Peekable<Chars<'_>>, x: u16, y: u16) -> Option<Elem> {
     let c = s.next().unwrap();
     assert_ne!(c, '\n');
     if c.is_ascii_alphabetic() || c.is_ascii_digit() {
-        Some(Node::new(c, x, y))
+        Some(Elem::Node(Node::new(c, x, y)))
+    } else if c == '#' {
+        Some(Elem::Obstacle(Vector([x.into(), y.into()])))
     } else if c == '.' {
         None
     } else {
@@ -50,7 +117,13 @@ fn trailer(s: &mut Peekable<Chars<'_>>) {
 
 #[cfg(test)]
 mod tests {
-    use crate::day08::{node::Node, parse::parse};
+    use std::collections::HashSet;
+
+    use crate::day08::{
+        node::Node,
+        parse::{parse, parse_space, parse_volume},
+        vector::Vector,
+    };
 
     #[test]
     fn test_parse() {
@@ -72,4 +145,51 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn test_parse_space() {
+        let (nodes, space) = parse_space("a#.\n..#\n");
+        assert_eq!(nodes, vec![Node::new('a', 0, 0)]);
+        assert_eq!(space.size, [3, 2]);
+        assert_eq!(
+            space.obstacles,
+            HashSet::from([Vector([1, 0]), Vector([2, 1])])
+        );
+        assert_eq!(parse("a#.\n..#\n"), (nodes, 3, 2));
+    }
+
+    #[test]
+    fn test_parse_volume() {
+        let input = "a..
+...
+
+...
+.a.
+";
+        let (nodes, space) = parse_volume(input);
+        assert_eq!(space.size, [3, 2, 2]);
+        assert_eq!(
+            nodes,
+            vec![
+                Node {
+                    code: 'a',
+                    pos: Vector([0, 0, 0])
+                },
+                Node {
+                    code: 'a',
+                    pos: Vector([1, 1, 1])
+                }
+            ]
+        );
+        let (nodes, space) = parse_volume(".0.\n\n.#.\n");
+        assert_eq!(space.size, [3, 1, 2]);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(space.obstacles, HashSet::from([Vector([1, 0, 1])]));
+    }
+
+    #[test]
+    #[should_panic(expected = "different heights")]
+    fn test_parse_volume_heights() {
+        parse_volume("...\n\n...\n...\n");
+    }
 }
@@ -0,0 +1,142 @@
+//! Where each antinode came from: which pairs of antennas put one there, and
+//! how the frequencies add up.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::{
+    node::{AntiNode, Node},
+    resonance::Resonance,
+    space::Space,
+    vector::Vector,
+};
+
+/// Two antennas of the same frequency.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Pair {
+    pub code: char,
+    pub a: Vector<2>,
+    pub b: Vector<2>,
+}
+
+/// How one frequency did.
+#[derive(Clone, PartialEq, Debug)]
+pub struct FrequencyStats {
+    pub code: char,
+    pub antennas: usize,
+    pub pairs: usize,
+    /// Distinct antinodes from any of this frequency's pairs.
+    pub antinodes: usize,
+    /// For each other frequency with an antinode in the same place, how
+    /// many places.
+    pub overlaps: BTreeMap<char, usize>,
+}
+
+pub struct Provenance {
+    sources: HashMap<AntiNode, Vec<Pair>>,
+    stats: BTreeMap<char, FrequencyStats>,
+}
+
+impl Provenance {
+    pub fn new(resonance: &Resonance, nodes: &[Node], space: &Space) -> Self {
+        let mut sources: HashMap<AntiNode, Vec<Pair>> = HashMap::new();
+        let mut stats: BTreeMap<char, FrequencyStats> = BTreeMap::new();
+        for (i, a) in nodes.iter().enumerate() {
+            let stats = stats.entry(a.code).or_insert(FrequencyStats {
+                code: a.code,
+                antennas: 0,
+                pairs: 0,
+                antinodes: 0,
+                overlaps: BTreeMap::new(),
+            });
+            stats.antennas += 1;
+            for b in nodes[i + 1..].iter().filter(|b| b.code == a.code) {
+                stats.pairs += 1;
+                let mut antinodes: HashSet<AntiNode> = HashSet::new();
+                resonance.pair(a.pos, b.pos, space, &mut antinodes);
+                let pair = Pair {
+                    code: a.code,
+                    a: a.pos,
+                    b: b.pos,
+                };
+                for antinode in antinodes {
+                    sources.entry(antinode).or_default().push(pair);
+                }
+            }
+        }
+        for pairs in sources.values() {
+            let mut codes = Vec::from_iter(pairs.iter().map(|p| p.code));
+            codes.sort();
+            codes.dedup();
+            for code in &codes {
+                let stats = stats.get_mut(code).unwrap();
+                stats.antinodes += 1;
+                for other in codes.iter().filter(|c| *c != code) {
+                    *stats.overlaps.entry(*other).or_default() += 1;
+                }
+            }
+        }
+        Self { sources, stats }
+    }
+
+    /// Every pair with an antinode at `pos`, in input order.
+    pub fn at(&self, pos: AntiNode) -> &[Pair] {
+        self.sources.get(&pos).map_or(&[], |pairs| pairs.as_slice())
+    }
+
+    /// Every frequency, in order.
+    pub fn stats(&self) -> impl Iterator<Item = &FrequencyStats> {
+        self.stats.values()
+    }
+
+    /// How many places have an antinode.
+    pub fn count(&self) -> usize {
+        self.sources.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::day08::{
+        node::AntiNode, parse::parse_space, resonance::Resonance, tests::EXAMPLE, vector::Vector,
+    };
+
+    use super::{Pair, Provenance};
+
+    #[test]
+    fn test_at() {
+        let (nodes, space) = parse_space(EXAMPLE);
+        let provenance = Provenance::new(&Resonance::part1(), &nodes, &space);
+        assert_eq!(provenance.count(), 14);
+        // Under the top `A`.
+        assert_eq!(
+            provenance.at(AntiNode::new(6, 5)),
+            [Pair {
+                code: '0',
+                a: Vector([8, 1]),
+                b: Vector([7, 3])
+            }]
+        );
+        assert_eq!(provenance.at(AntiNode::new(0, 0)), []);
+    }
+
+    #[test]
+    fn test_stats() {
+        let (nodes, space) = parse_space(EXAMPLE);
+        let provenance = Provenance::new(&Resonance::part2(), &nodes, &space);
+        let stats = Vec::from_iter(provenance.stats());
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            (stats[0].code, stats[0].antennas, stats[0].pairs),
+            ('0', 4, 6)
+        );
+        assert_eq!(
+            (stats[1].code, stats[1].antennas, stats[1].pairs),
+            ('A', 3, 3)
+        );
+        let shared = stats[0].overlaps[&'A'];
+        assert_eq!(stats[1].overlaps, BTreeMap::from([('0', shared)]));
+        assert_eq!(stats[0].antinodes + stats[1].antinodes - shared, 34);
+    }
+}
@@ -0,0 +1,251 @@
+//! Drawing the antennas with their antinodes, to see what the geometry did.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::{self, Write},
+};
+
+use crate::vec2::Vec2;
+
+use super::{
+    node::{AntiNode, Node},
+    resonance::Resonance,
+    space::Space,
+    vector::Vector,
+};
+
+/// ANSI foreground colors to cycle through, one per frequency.
+const ANSI: [u8; 12] = [31, 32, 33, 34, 35, 36, 91, 92, 93, 94, 95, 96];
+
+pub struct Overlay<'a> {
+    nodes: &'a [Node],
+    space: &'a Space,
+    antinodes: BTreeMap<char, HashSet<AntiNode>>,
+}
+
+impl<'a> Overlay<'a> {
+    pub fn new(nodes: &'a [Node], space: &'a Space, resonance: &Resonance) -> Self {
+        Self {
+            nodes,
+            space,
+            antinodes: resonance.by_code(nodes, space),
+        }
+    }
+
+    /// Which of the frequencies `code` is, counting from zero in order.
+    fn rank(&self, code: char) -> usize {
+        self.antinodes.keys().position(|c| *c == code).unwrap()
+    }
+
+    /// Every frequency with an antinode at `pos`.
+    fn codes_at(&self, pos: AntiNode) -> Vec<char> {
+        Vec::from_iter(
+            self.antinodes
+                .iter()
+                .filter(|(_, antinodes)| antinodes.contains(&pos))
+                .map(|(code, _)| *code),
+        )
+    }
+
+    /// The input map with `#` wherever there's an antinode, unless there's
+    /// an antenna there to show instead, and `%` for obstacles. With
+    /// `color`, each frequency gets its own color, and antinodes shared by
+    /// several are bold.
+    pub fn text(&self, color: bool) -> String {
+        let mut text = String::new();
+        for y in 0..self.space.height() {
+            for x in 0..self.space.width() {
+                let pos = AntiNode::new(x, y);
+                let antenna = self
+                    .nodes
+                    .iter()
+                    .find(|n| n.pos == Vector([x as i32, y as i32]));
+                let codes = self.codes_at(pos);
+                let (c, style) = match (antenna, codes.as_slice()) {
+                    (Some(node), _) => (node.code, Some(self.ansi(node.code))),
+                    (None, []) if self.space.blocked(Vector([x as i32, y as i32])) => {
+                        ('%', Some("2".to_string()))
+                    }
+                    (None, []) => ('.', None),
+                    (None, [code]) => ('#', Some(self.ansi(*code))),
+                    (None, _) => ('#', Some("1".to_string())),
+                };
+                match style {
+                    Some(style) if color => text += &format!("\x1b[{}m{}\x1b[0m", style, c),
+                    _ => text.push(c),
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    fn ansi(&self, code: char) -> String {
+        ANSI[self.rank(code) % ANSI.len()].to_string()
+    }
+
+    fn hue(&self, code: char) -> usize {
+        self.rank(code) * 360 / self.antinodes.len()
+    }
+
+    /// A picture one unit per square, with a line through every pair of
+    /// antennas that resonate, dashed past the antennas to the edge of the
+    /// map, a square shaded for each antinode, and a dot for each antenna.
+    pub fn write_svg<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(
+            w,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+            self.space.width() as u32 * 10,
+            self.space.height() as u32 * 10,
+            self.space.width(),
+            self.space.height()
+        )?;
+        writeln!(
+            w,
+            r#"<rect width="{}" height="{}" fill="white"/>"#,
+            self.space.width(),
+            self.space.height()
+        )?;
+        let mut obstacles = Vec::from_iter(&self.space.obstacles);
+        obstacles.sort_by_key(|pos| (pos.y(), pos.x()));
+        for pos in obstacles {
+            writeln!(
+                w,
+                r#"<rect x="{}" y="{}" width="1" height="1" fill="grey"/>"#,
+                pos.x(),
+                pos.y()
+            )?;
+        }
+        for (code, antinodes) in &self.antinodes {
+            let mut antinodes = Vec::from_iter(antinodes);
+            antinodes.sort_by_key(|a| (a.y(), a.x()));
+            for antinode in antinodes {
+                writeln!(
+                    w,
+                    r#"<rect x="{}" y="{}" width="1" height="1" fill="hsl({}, 70%, 50%)" fill-opacity="0.35"/>"#,
+                    antinode.x(),
+                    antinode.y(),
+                    self.hue(*code)
+                )?;
+            }
+        }
+        for (i, a) in self.nodes.iter().enumerate() {
+            for b in self.nodes[i + 1..].iter().filter(|b| b.code == a.code) {
+                let stroke = format!(r#"stroke="hsl({}, 70%, 40%)""#, self.hue(a.code));
+                let step = (b.pos - a.pos).reduce();
+                let (start, end) = (self.edge(a.pos, step * -1), self.edge(b.pos, step));
+                line(
+                    w,
+                    start,
+                    end,
+                    &stroke,
+                    r#"stroke-width="0.05" stroke-dasharray="0.2""#,
+                )?;
+                line(
+                    w,
+                    a.pos.into(),
+                    b.pos.into(),
+                    &stroke,
+                    r#"stroke-width="0.1""#,
+                )?;
+            }
+        }
+        for node in self.nodes {
+            writeln!(
+                w,
+                r#"<circle cx="{}.5" cy="{}.5" r="0.4" fill="hsl({}, 70%, 40%)"/>"#,
+                node.pos.x(),
+                node.pos.y(),
+                self.hue(node.code)
+            )?;
+            writeln!(
+                w,
+                r#"<text x="{}.5" y="{}.5" font-size="0.6" fill="white" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                node.pos.x(),
+                node.pos.y(),
+                node.code
+            )?;
+        }
+        writeln!(w, "</svg>")
+    }
+
+    /// The last square on the map going from `pos` in steps of `step`.
+    fn edge(&self, mut pos: Vector<2>, step: Vector<2>) -> Vec2<i32> {
+        while self.space.at(pos + step).is_some() {
+            pos = pos + step;
+        }
+        pos.into()
+    }
+}
+
+fn line<W: Write>(
+    w: &mut W,
+    a: Vec2<i32>,
+    b: Vec2<i32>,
+    stroke: &str,
+    style: &str,
+) -> io::Result<()> {
+    writeln!(
+        w,
+        r#"<line x1="{}.5" y1="{}.5" x2="{}.5" y2="{}.5" {} {}/>"#,
+        a.x, a.y, b.x, b.y, stroke, style
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day08::{parse::parse_space, resonance::Resonance, tests::EXAMPLE};
+
+    use super::Overlay;
+
+    #[test]
+    fn test_text() {
+        let (nodes, space) = parse_space(EXAMPLE);
+        let overlay = Overlay::new(&nodes, &space, &Resonance::part1());
+        // As drawn in the puzzle.
+        assert_eq!(
+            overlay.text(false),
+            "......#....#
+...#....0...
+....#0....#.
+..#....0....
+....0....#..
+.#....A.....
+...#........
+#......#....
+........A...
+.........A..
+..........#.
+..........#.
+"
+        );
+        let colored = overlay.text(true);
+        assert!(colored.starts_with("......\x1b[31m#\x1b[0m"));
+        // Top right, both `0` and `A` have an antinode.
+        let (nodes, space) = parse_space("00.\n.A.\nA..\n");
+        let overlay = Overlay::new(&nodes, &space, &Resonance::part2());
+        assert_eq!(overlay.text(false), "00#\n.A.\nA..\n");
+        assert!(overlay.text(true).contains("\x1b[1m#\x1b[0m"));
+        let (nodes, space) = parse_space("a.a#.\n");
+        let overlay = Overlay::new(&nodes, &space, &Resonance::part1());
+        assert_eq!(overlay.text(false), "a.a%#\n");
+    }
+
+    #[test]
+    fn test_svg() {
+        let (nodes, space) = parse_space("....\n.a..\n..a.\n....\n");
+        let overlay = Overlay::new(&nodes, &space, &Resonance::part1());
+        let mut svg: Vec<u8> = Vec::new();
+        overlay.write_svg(&mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="40" viewBox="0 0 4 4">"#
+        ));
+        assert!(svg.contains(r#"<rect x="0" y="0" width="1" height="1""#));
+        assert!(svg.contains(r#"<rect x="3" y="3" width="1" height="1""#));
+        assert!(svg.contains(r#"<line x1="0.5" y1="0.5" x2="3.5" y2="3.5""#));
+        assert!(svg.contains(r#"<line x1="1.5" y1="1.5" x2="2.5" y2="2.5""#));
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
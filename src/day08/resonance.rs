@@ -0,0 +1,375 @@
+//! Where a pair of antennas puts its antinodes. Everything is done in whole
+//! steps along the line through the pair: with `step` the smallest lattice
+//! step from `a` towards `b`, and `b = a + gap * step`, the point
+//! `a + t * step` is `|t|` steps from `a` and `|t - gap|` steps from `b`.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    str::FromStr,
+};
+
+use super::{
+    node::{AntiNode, Node},
+    space::Space,
+    vector::Vector,
+};
+
+/// An antinode twice as far from one antenna as the other is `2:1`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Ratio {
+    pub far: u32,
+    pub near: u32,
+}
+
+impl Ratio {
+    #[inline]
+    pub fn new(far: u32, near: u32) -> Self {
+        Self { far, near }
+    }
+}
+
+impl FromStr for Ratio {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (far, near) = s
+            .split_once(':')
+            .ok_or_else(|| format!("expected FAR:NEAR, got '{}'", s))?;
+        let far: u32 = far.parse().map_err(|e| format!("bad ratio: {}", e))?;
+        let near: u32 = near.parse().map_err(|e| format!("bad ratio: {}", e))?;
+        if far == 0 || near == 0 {
+            Err("neither side of a ratio can be zero".to_string())
+        } else {
+            Ok(Self { far, near })
+        }
+    }
+}
+
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.far, self.near)
+    }
+}
+
+/// The rules for where antinodes go.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Resonance {
+    /// Distance ratios between the two antennas to put antinodes at.
+    pub ratios: Vec<Ratio>,
+    /// Every lattice point on the line, antennas included, whatever the
+    /// ratio.
+    pub harmonics: bool,
+    /// Whether points strictly between the two antennas count.
+    pub interior: bool,
+    /// Only points at most this many steps from the nearer antenna.
+    pub steps: Option<u32>,
+    /// Whether obstacles get in the way: antennas that can't see each other
+    /// don't resonate, and lines stop at the first obstacle past either
+    /// antenna.
+    pub blocking: bool,
+}
+
+impl Resonance {
+    pub fn part1() -> Self {
+        Self {
+            ratios: vec![Ratio::new(2, 1)],
+            harmonics: false,
+            interior: false,
+            steps: None,
+            blocking: false,
+        }
+    }
+
+    pub fn part2() -> Self {
+        Self {
+            ratios: Vec::new(),
+            harmonics: true,
+            interior: true,
+            steps: None,
+            blocking: false,
+        }
+    }
+
+    pub fn antinodes<const D: usize>(
+        &self,
+        nodes: &[Node<D>],
+        space: &Space<D>,
+    ) -> HashSet<AntiNode<D>> {
+        let mut antinodes: HashSet<AntiNode<D>> = HashSet::new();
+        for (i, a) in nodes.iter().enumerate() {
+            for b in &nodes[i + 1..] {
+                if a.code == b.code {
+                    self.pair(a.pos, b.pos, space, &mut antinodes);
+                }
+            }
+        }
+        antinodes
+    }
+
+    /// The antinodes of each frequency on its own.
+    pub fn by_code<const D: usize>(
+        &self,
+        nodes: &[Node<D>],
+        space: &Space<D>,
+    ) -> BTreeMap<char, HashSet<AntiNode<D>>> {
+        let mut by_code: BTreeMap<char, HashSet<AntiNode<D>>> = BTreeMap::new();
+        for (i, a) in nodes.iter().enumerate() {
+            let antinodes = by_code.entry(a.code).or_default();
+            for b in &nodes[i + 1..] {
+                if a.code == b.code {
+                    self.pair(a.pos, b.pos, space, antinodes);
+                }
+            }
+        }
+        by_code
+    }
+
+    /// Add the antinodes of the antennas at `a` and `b`.
+    pub fn pair<const D: usize>(
+        &self,
+        a: Vector<D>,
+        b: Vector<D>,
+        space: &Space<D>,
+        antinodes: &mut HashSet<AntiNode<D>>,
+    ) {
+        let step = (b - a).reduce();
+        let gap = (b - a).steps(step);
+        // Anywhere `a + step * t` doesn't fit is well off the map.
+        let point = |t: i32| a.checked_step(step, t);
+        let clear = |t: i32| point(t).is_none_or(|pos| !space.blocked(pos));
+        // How far the line goes past each antenna before it's cut off.
+        let (low, high) = if !self.blocking {
+            (i32::MIN, i32::MAX)
+        } else if (1..gap).all(clear) {
+            let reach = |mut t: i32, dir: i32| {
+                while point(t + dir).and_then(|pos| space.at(pos)).is_some() && clear(t + dir) {
+                    t += dir;
+                }
+                t
+            };
+            (reach(0, -1), reach(gap, 1))
+        } else {
+            return;
+        };
+        let at = |t: i32| {
+            point(t)
+                .and_then(|pos| space.at(pos))
+                .filter(|_| low <= t && t <= high && self.within(t, gap))
+        };
+        for t in self.ratio_points(gap) {
+            if let Some(antinode) = at(t) {
+                antinodes.insert(antinode);
+            }
+        }
+        if !self.harmonics {
+            return;
+        }
+        // Out from each antenna until it leaves the map or goes too far.
+        for (start, dir) in [(0, -1), (gap, 1)] {
+            let mut t = start;
+            while let Some(antinode) = at(t) {
+                antinodes.insert(antinode);
+                t += dir;
+            }
+        }
+        if self.interior {
+            antinodes.extend((1..gap).filter_map(at));
+        }
+    }
+
+    /// Every `t` that's at one of the ratios, with either antenna as the far
+    /// one.
+    fn ratio_points(&self, gap: i32) -> Vec<i32> {
+        let mut points: Vec<i32> = Vec::new();
+        for ratio in &self.ratios {
+            let (far, near) = (ratio.far as i64, ratio.near as i64);
+            for (p, q) in [(far, near), (near, far)] {
+                // `|t| : |t - gap| = p : q` past whichever antenna is nearer.
+                if p != q {
+                    points.extend(exact(gap as i64 * p, p - q));
+                }
+                // The same, but between the two.
+                if self.interior {
+                    points.extend(exact(gap as i64 * p, p + q));
+                }
+            }
+        }
+        points
+    }
+
+    fn within(&self, t: i32, gap: i32) -> bool {
+        let interior = 0 < t && t < gap;
+        let steps = t.unsigned_abs().min(t.abs_diff(gap));
+        (self.interior || !interior) && self.steps.is_none_or(|max| steps <= max)
+    }
+}
+
+/// `n / d`, if it divides exactly and fits.
+fn exact(n: i64, d: i64) -> Option<i32> {
+    if n % d == 0 {
+        i32::try_from(n / d).ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::day08::{
+        node::{AntiNode, Node},
+        parse::{parse, parse_space, parse_volume},
+        space::Space,
+    };
+
+    use super::{Ratio, Resonance};
+
+    fn points(rules: &Resonance, nodes: &[Node], width: u16) -> HashSet<[u16; 2]> {
+        HashSet::from_iter(
+            rules
+                .antinodes(nodes, &Space::open([width, 1]))
+                .iter()
+                .map(|a| a.pos),
+        )
+    }
+
+    #[test]
+    fn test_ratio() {
+        assert_eq!("3:2".parse(), Ok(Ratio::new(3, 2)));
+        assert_eq!(Ratio::new(3, 2).to_string(), "3:2");
+        assert!("3".parse::<Ratio>().is_err());
+        assert!("0:1".parse::<Ratio>().is_err());
+        assert!("a:1".parse::<Ratio>().is_err());
+    }
+
+    #[test]
+    fn test_ratios() {
+        // One row, antennas at 6 and 12.
+        let nodes = [Node::new('a', 6, 0), Node::new('a', 12, 0)];
+        let part1 = Resonance::part1();
+        assert_eq!(points(&part1, &nodes, 20), HashSet::from([[0, 0], [18, 0]]));
+        let interior = Resonance {
+            interior: true,
+            ..part1
+        };
+        assert_eq!(
+            points(&interior, &nodes, 20),
+            HashSet::from([[0, 0], [8, 0], [10, 0], [18, 0]])
+        );
+        let rules = Resonance {
+            ratios: vec![Ratio::new(3, 1), Ratio::new(3, 2)],
+            ..Resonance::part1()
+        };
+        // 3:1 is 3 past either one, 3:2 is 12 past.
+        assert_eq!(
+            points(&rules, &nodes, 30),
+            HashSet::from([[3, 0], [15, 0], [24, 0]])
+        );
+        // 1:1 is only ever in the middle.
+        let middle = Resonance {
+            ratios: vec![Ratio::new(1, 1)],
+            interior: true,
+            ..Resonance::part1()
+        };
+        assert_eq!(points(&middle, &nodes, 20), HashSet::from([[9, 0]]));
+        // A billion steps out, well past anywhere an `i32` can reach.
+        let (nodes, space) = parse_space("a....\n.....\n.....\n.a...\n");
+        let far = Resonance {
+            ratios: vec![Ratio::new(1_000_000_000, 999_999_999)],
+            ..Resonance::part1()
+        };
+        assert!(far.antinodes(&nodes, &space).is_empty());
+    }
+
+    #[test]
+    fn test_harmonics() {
+        let nodes = [Node::new('a', 4, 0), Node::new('a', 6, 0)];
+        let all = Resonance::part2();
+        assert_eq!(points(&all, &nodes, 9).len(), 9);
+        let outside = Resonance {
+            interior: false,
+            ..Resonance::part2()
+        };
+        assert_eq!(points(&outside, &nodes, 9).len(), 8);
+        let near = Resonance {
+            steps: Some(1),
+            ..Resonance::part2()
+        };
+        assert_eq!(
+            points(&near, &nodes, 9),
+            HashSet::from([[3, 0], [4, 0], [5, 0], [6, 0], [7, 0]])
+        );
+    }
+
+    /// The puzzle's two parts are just two sets of rules.
+    #[test]
+    fn test_parts() {
+        let (nodes, width, height) = parse(crate::day08::tests::EXAMPLE);
+        assert_eq!(
+            Resonance::part1()
+                .antinodes(&nodes, &Space::open([width, height]))
+                .len(),
+            14
+        );
+        assert_eq!(
+            Resonance::part2()
+                .antinodes(&nodes, &Space::open([width, height]))
+                .len(),
+            34
+        );
+    }
+
+    #[test]
+    fn test_volume() {
+        // Two slices, antennas on a diagonal through both.
+        let (nodes, space) = parse_volume("a...\n....\n....\n\n....\n.a..\n....\n");
+        assert_eq!(Resonance::part1().antinodes(&nodes, &space), HashSet::new());
+        let (nodes, space) = parse_volume("a..\n...\n...\n\n...\n.a.\n...\n\n...\n...\n...\n");
+        assert_eq!(
+            Resonance::part1().antinodes(&nodes, &space),
+            HashSet::from([AntiNode { pos: [2, 2, 2] }])
+        );
+        assert_eq!(Resonance::part2().antinodes(&nodes, &space).len(), 3);
+    }
+
+    #[test]
+    fn test_blocking() {
+        let blocking = |rules: Resonance| Resonance {
+            blocking: true,
+            ..rules
+        };
+        // Nothing in the way, so the same as usual.
+        let (nodes, space) = parse_space(".a.a...\n");
+        assert_eq!(
+            blocking(Resonance::part1()).antinodes(&nodes, &space),
+            Resonance::part1().antinodes(&nodes, &space)
+        );
+        // Can't see each other.
+        let (nodes, space) = parse_space("a#a....\n");
+        assert!(blocking(Resonance::part2())
+            .antinodes(&nodes, &space)
+            .is_empty());
+        assert_eq!(Resonance::part2().antinodes(&nodes, &space).len(), 7);
+        // Cut off just past the right one, but not the left.
+        let (nodes, space) = parse_space("..a.a#...\n");
+        let antinodes = blocking(Resonance::part2()).antinodes(&nodes, &space);
+        assert_eq!(
+            HashSet::from_iter(antinodes.iter().map(|a| a.pos)),
+            HashSet::from([[0, 0], [1, 0], [2, 0], [3, 0], [4, 0]])
+        );
+        let (nodes, space) = parse_space("a.a.#.\n");
+        assert!(blocking(Resonance::part1())
+            .antinodes(&nodes, &space)
+            .is_empty());
+        // The puzzle example with an obstacle where an antinode was.
+        let mut input = String::from(crate::day08::tests::EXAMPLE);
+        input.replace_range(6..7, "#");
+        let (nodes, space) = parse_space(&input);
+        assert_eq!(
+            blocking(Resonance::part1()).antinodes(&nodes, &space).len(),
+            13
+        );
+    }
+}
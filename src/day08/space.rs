@@ -0,0 +1,45 @@
+//! The map or volume antennas sit in: how big it is, and which squares
+//! are blocked.
+
+use std::collections::HashSet;
+
+use super::{node::AntiNode, vector::Vector};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Space<const D: usize = 2> {
+    pub size: [u16; D],
+    pub obstacles: HashSet<Vector<D>>,
+}
+
+impl<const D: usize> Space<D> {
+    /// Nothing in the way.
+    pub fn open(size: [u16; D]) -> Self {
+        Self {
+            size,
+            obstacles: HashSet::new(),
+        }
+    }
+
+    /// The antinode at `pos`, if that's inside.
+    #[inline]
+    pub fn at(&self, pos: Vector<D>) -> Option<AntiNode<D>> {
+        AntiNode::from_vector(pos, self.size)
+    }
+
+    #[inline]
+    pub fn blocked(&self, pos: Vector<D>) -> bool {
+        self.obstacles.contains(&pos)
+    }
+}
+
+impl Space {
+    #[inline]
+    pub fn width(&self) -> u16 {
+        self.size[0]
+    }
+
+    #[inline]
+    pub fn height(&self) -> u16 {
+        self.size[1]
+    }
+}
@@ -0,0 +1,115 @@
+//! Positions on a lattice with any number of dimensions, so the same
+//! geometry works on maps and on stacks of them.
+
+use std::ops;
+
+use crate::vec2::{Gcd, Vec2};
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct Vector<const D: usize>(pub [i32; D]);
+
+impl<const D: usize> Vector<D> {
+    /// See [`Gcd::reduce`].
+    pub fn reduce(self) -> Self {
+        Self(i32::reduce(self.0))
+    }
+
+    /// `self + step * t`, or `None` if that doesn't fit.
+    pub fn checked_step(self, step: Self, t: i32) -> Option<Self> {
+        let mut pos = self;
+        for (n, step) in pos.0.iter_mut().zip(step.0) {
+            *n = step.checked_mul(t)?.checked_add(*n)?;
+        }
+        Some(pos)
+    }
+
+    /// How many times `step` goes into `self`, given that it goes exactly.
+    pub fn steps(self, step: Self) -> i32 {
+        (0..D)
+            .find(|i| step.0[*i] != 0)
+            .map_or(0, |i| self.0[i] / step.0[i])
+    }
+}
+
+impl Vector<2> {
+    #[inline]
+    pub fn x(self) -> i32 {
+        self.0[0]
+    }
+
+    #[inline]
+    pub fn y(self) -> i32 {
+        self.0[1]
+    }
+}
+
+impl From<Vector<2>> for Vec2<i32> {
+    fn from(v: Vector<2>) -> Self {
+        Self::new(v.x(), v.y())
+    }
+}
+
+impl<const D: usize> ops::Add for Vector<D> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(std::array::from_fn(|i| self.0[i] + rhs.0[i]))
+    }
+}
+
+impl<const D: usize> ops::Sub for Vector<D> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(std::array::from_fn(|i| self.0[i] - rhs.0[i]))
+    }
+}
+
+impl<const D: usize> ops::Mul<i32> for Vector<D> {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self::Output {
+        Self(self.0.map(|n| n * rhs))
+    }
+}
+
+impl<const D: usize> ops::Div<i32> for Vector<D> {
+    type Output = Self;
+
+    fn div(self, rhs: i32) -> Self::Output {
+        Self(self.0.map(|n| n / rhs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vec2::Vec2;
+
+    use super::Vector;
+
+    #[test]
+    fn test_math() {
+        assert_eq!(Vector([1, 2, 3]) + Vector([2, 3, 4]), Vector([3, 5, 7]));
+        assert_eq!(Vector([2, 7]) - Vector([5, 4]), Vector([-3, 3]));
+        assert_eq!(Vector([1, -2, 3]) * 2, Vector([2, -4, 6]));
+        assert_eq!(Vector([6, 4]).steps(Vector([3, 2])), 2);
+        assert_eq!(Vector([0, -4, 0]).steps(Vector([0, 2, 0])), -2);
+        assert_eq!(Vec2::from(Vector([5, 4])), Vec2::new(5, 4));
+        assert_eq!(
+            Vector([1, 2]).checked_step(Vector([3, -1]), 4),
+            Some(Vector([13, -2]))
+        );
+        assert_eq!(Vector([0, 0]).checked_step(Vector([1, 3]), 1 << 30), None);
+        assert_eq!(Vector([i32::MAX]).checked_step(Vector([1]), 1), None);
+    }
+
+    #[test]
+    fn test_reduce() {
+        assert_eq!(Vector([4, -6]).reduce(), Vector([2, -3]));
+        assert_eq!(Vector([0, -5]).reduce(), Vector([0, -1]));
+        assert_eq!(Vector([3, 5]).reduce(), Vector([3, 5]));
+        assert_eq!(Vector([0, 0]).reduce(), Vector([0, 0]));
+        assert_eq!(Vector([6, -9, 12]).reduce(), Vector([2, -3, 4]));
+        assert_eq!(Vector([4, 6, 5]).reduce(), Vector([4, 6, 5]));
+    }
+}
@@ -13,3 +13,5 @@ pub mod day06;
 pub mod day07;
 
 pub mod day08;
+
+pub mod vec2;
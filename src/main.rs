@@ -1,8 +1,14 @@
 //! Advent of Code 2024 by Troy F.
 
 use aoc2024::{
-    day01::day01, day02::day02, day03::day03, day04::day04, day05::day05, day06::day06,
-    day07::day07, day08::day08,
+    day01::day01,
+    day02::day02,
+    day03::{self, day03},
+    day04::day04,
+    day05::day05,
+    day06::{self, day06},
+    day07::{self, day07},
+    day08::{self, day08},
 };
 use clap::Parser;
 
@@ -11,12 +17,12 @@ fn main() {
     match args.day {
         1 => day01(),
         2 => day02(),
-        3 => day03(),
+        3 => day03(&args.day03),
         4 => day04(),
         5 => day05(),
-        6 => day06(),
-        7 => day07(),
-        8 => day08(),
+        6 => day06(&args.day06),
+        7 => day07(&args.day07),
+        8 => day08(&args.day08),
         _ => panic!("bad day"),
     }
 }
@@ -25,4 +31,16 @@ fn main() {
 #[command(version, about)]
 struct Args {
     day: usize,
+
+    #[command(flatten, next_help_heading = "Day 3")]
+    day03: day03::Options,
+
+    #[command(flatten, next_help_heading = "Day 6")]
+    day06: day06::Options,
+
+    #[command(flatten, next_help_heading = "Day 7")]
+    day07: day07::Options,
+
+    #[command(flatten, next_help_heading = "Day 8")]
+    day08: day08::Options,
 }
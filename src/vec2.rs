@@ -0,0 +1,272 @@
+//! A 2D vector for any of the geometry puzzles, over integers or floats.
+
+use std::ops;
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Default, Debug)]
+pub struct Vec2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Vec2<T> {
+    #[inline]
+    pub fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<T: Copy + ops::Sub<Output = T>> Vec2<T> {
+    /// `other` as seen from `self`.
+    #[inline]
+    pub fn relative(self, other: Self) -> Self {
+        other - self
+    }
+}
+
+impl<T: Copy + ops::Add<Output = T> + ops::Mul<Output = T>> Vec2<T> {
+    #[inline]
+    pub fn dot(self, rhs: Self) -> T {
+        self.x * rhs.x + self.y * rhs.y
+    }
+}
+
+impl<T: Copy + ops::Sub<Output = T> + ops::Mul<Output = T>> Vec2<T> {
+    /// The perp-dot product: positive if `rhs` is a quarter turn or less
+    /// from `self` going from the x axis towards the y axis, negative the
+    /// other way, and zero if they're parallel.
+    #[inline]
+    pub fn cross(self, rhs: Self) -> T {
+        self.x * rhs.y - self.y * rhs.x
+    }
+}
+
+impl<T: Copy + ops::Neg<Output = T>> Vec2<T> {
+    /// A quarter turn from the x axis towards the y axis, which is
+    /// clockwise on a map with y going down.
+    #[inline]
+    pub fn rotate90(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+}
+
+impl<T: Copy + PartialOrd> Vec2<T> {
+    /// The smaller of each component.
+    #[inline]
+    pub fn min(self, other: Self) -> Self {
+        let pick = |a: T, b: T| if b < a { b } else { a };
+        Self::new(pick(self.x, other.x), pick(self.y, other.y))
+    }
+
+    /// The bigger of each component.
+    #[inline]
+    pub fn max(self, other: Self) -> Self {
+        let pick = |a: T, b: T| if b > a { b } else { a };
+        Self::new(pick(self.x, other.x), pick(self.y, other.y))
+    }
+}
+
+impl Vec2<f64> {
+    pub fn len(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    #[inline]
+    pub fn dist(self, other: Self) -> f64 {
+        self.relative(other).len()
+    }
+
+    #[inline]
+    pub fn normal(self) -> Self {
+        self / self.len()
+    }
+}
+
+/// Integers with a greatest common divisor.
+pub trait Gcd: Copy + PartialEq + Default + ops::Div<Output = Self> {
+    /// Never negative, and zero only if both are.
+    fn gcd(self, other: Self) -> Self;
+
+    /// The smallest step in the same direction as `ns` that still lands on
+    /// whole numbers, so stepping by it visits every lattice point on the
+    /// line.
+    fn reduce<const D: usize>(ns: [Self; D]) -> [Self; D] {
+        let gcd = ns.iter().fold(Self::default(), |gcd, n| n.gcd(gcd));
+        if gcd == Self::default() {
+            ns
+        } else {
+            ns.map(|n| n / gcd)
+        }
+    }
+}
+
+macro_rules! gcd {
+    ($($t:ty)*) => {
+        $(
+            impl Gcd for $t {
+                #[allow(unused_comparisons)]
+                fn gcd(self, other: Self) -> Self {
+                    let (mut a, mut b) = (self, other);
+                    while b != 0 {
+                        (a, b) = (b, a % b);
+                    }
+                    if a < 0 {
+                        0 - a
+                    } else {
+                        a
+                    }
+                }
+            }
+        )*
+    };
+}
+
+gcd!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+
+impl<T: Gcd> Vec2<T> {
+    /// See [`Gcd::reduce`].
+    pub fn reduce(self) -> Self {
+        let [x, y] = T::reduce([self.x, self.y]);
+        Self::new(x, y)
+    }
+}
+
+impl<T> From<(T, T)> for Vec2<T> {
+    #[inline]
+    fn from((x, y): (T, T)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl<T> From<Vec2<T>> for (T, T) {
+    #[inline]
+    fn from(v: Vec2<T>) -> Self {
+        (v.x, v.y)
+    }
+}
+
+impl<T: ops::Add<Output = T>> ops::Add for Vec2<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl<T: ops::Sub<Output = T>> ops::Sub for Vec2<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl<T: ops::AddAssign> ops::AddAssign for Vec2<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+    }
+}
+
+impl<T: ops::SubAssign> ops::SubAssign for Vec2<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+    }
+}
+
+impl<T: ops::Neg<Output = T>> ops::Neg for Vec2<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl<T: ops::Mul<Output = T> + Copy> ops::Mul<T> for Vec2<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl<T: ops::Div<Output = T> + Copy> ops::Div<T> for Vec2<T> {
+    type Output = Self;
+
+    fn div(self, rhs: T) -> Self::Output {
+        Self::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gcd, Vec2};
+
+    #[test]
+    fn test_math() {
+        assert_eq!(
+            Vec2::new(1.0, 2.0) + Vec2::new(2.0, 3.0),
+            Vec2::new(3.0, 5.0)
+        );
+        assert_eq!(Vec2::new(1.0, 2.0) * 2.0, Vec2::new(2.0, 4.0));
+        assert_eq!(Vec2::new(5.0, 4.0) / 2.0, Vec2::new(2.5, 2.0));
+        assert_eq!(-Vec2::new(1, -2), Vec2::new(-1, 2));
+        let mut v = Vec2::new(1, 2);
+        v += Vec2::new(3, 4);
+        assert_eq!(v, Vec2::new(4, 6));
+        v -= Vec2::new(1, 1);
+        assert_eq!(v, Vec2::new(3, 5));
+        assert_eq!(Vec2::new(5, 4).relative(Vec2::new(2, 7)), Vec2::new(-3, 3));
+    }
+
+    #[test]
+    fn test_products() {
+        assert_eq!(Vec2::new(1, 2).dot(Vec2::new(3, 4)), 11);
+        assert_eq!(Vec2::new(1, 0).cross(Vec2::new(0, 1)), 1);
+        assert_eq!(Vec2::new(0, 1).cross(Vec2::new(1, 0)), -1);
+        assert_eq!(Vec2::new(2, 4).cross(Vec2::new(1, 2)), 0);
+        assert_eq!(Vec2::new(3.0, 4.0).len(), 5.0);
+        assert_eq!(Vec2::new(1.0, 1.0).dist(Vec2::new(4.0, 5.0)), 5.0);
+        assert_eq!(Vec2::new(0.0, 2.0).normal(), Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn test_rotate() {
+        let v = Vec2::new(1, 2);
+        assert_eq!(v.rotate90(), Vec2::new(-2, 1));
+        assert_eq!(v.rotate90().rotate90(), -v);
+        assert_eq!(v.rotate90().rotate90().rotate90().rotate90(), v);
+        assert_eq!(v.dot(v.rotate90()), 0);
+    }
+
+    #[test]
+    fn test_min_max() {
+        let (a, b) = (Vec2::new(1, 5), Vec2::new(3, 2));
+        assert_eq!(a.min(b), Vec2::new(1, 2));
+        assert_eq!(a.max(b), Vec2::new(3, 5));
+        assert_eq!(
+            Vec2::new(0.5, 2.0).min(Vec2::new(1.0, -1.0)),
+            Vec2::new(0.5, -1.0)
+        );
+    }
+
+    #[test]
+    fn test_tuple() {
+        assert_eq!(Vec2::from((3, -4)), Vec2::new(3, -4));
+        assert_eq!(<(i32, i32)>::from(Vec2::new(3, -4)), (3, -4));
+    }
+
+    #[test]
+    fn test_reduce() {
+        assert_eq!((-4i32).gcd(6), 2);
+        assert_eq!(0u8.gcd(0), 0);
+        assert_eq!(Vec2::new(4, -6).reduce(), Vec2::new(2, -3));
+        assert_eq!(Vec2::new(0, -5).reduce(), Vec2::new(0, -1));
+        assert_eq!(Vec2::new(3, 5).reduce(), Vec2::new(3, 5));
+        assert_eq!(Vec2::new(0, 0).reduce(), Vec2::new(0, 0));
+        assert_eq!(Vec2::new(12u64, 18).reduce(), Vec2::new(2, 3));
+        assert_eq!(Vec2::new(-6i64, -9).reduce(), Vec2::new(-2, -3));
+        assert_eq!(i32::reduce([6, -9, 12]), [2, -3, 4]);
+        assert_eq!(u8::reduce([0; 3]), [0; 3]);
+    }
+}